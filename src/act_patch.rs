@@ -2,8 +2,9 @@ use std::{fs, path::PathBuf, str::FromStr};
 
 use crate::{
     act_structs::{
-        get_js_constructor_from_acttype, get_ts_type_from_acttype,
-        get_typeinfo_operator_from_acttype, ParamAct, PatchAct, TypeAct,
+        get_js_constructor_from_acttype, get_literal_string, get_ts_type_from_acttype,
+        get_typeinfo_operator_from_acttype, get_unchecked_reasons, is_value_set_acttype,
+        LiteralAct, ParamAct, PatchAct, PropertyAct, TemplatePartAct, TupleElementAct, TypeAct,
     },
     args_parser::ActArgs,
     patch_index_helper::PatchIndexHelper,
@@ -29,94 +30,894 @@ impl FromStr for PatchType {
     }
 }
 
+#[derive(Debug, Clone, ValueEnum)]
+pub enum ArrayCheckMode {
+    /// Check every element
+    Full,
+    /// Check the first `array_sample_size` elements
+    Sample,
+    /// Only check that the value is an array
+    Shape,
+}
+
+pub fn gen_union_type_check(
+    types: &[TypeAct],
+    value: &str,
+    depth: usize,
+    args: &ActArgs,
+) -> Option<String> {
+    let mut types_check: Vec<String> = vec![];
+    for union_member in types {
+        // If one member can't be checked, the value could be of that member
+        types_check.push(gen_type_check(union_member, value, depth, args)?);
+    }
+    Some(format!("({})", types_check.join(" || ")))
+}
+
+pub fn gen_intersection_type_check(
+    types: &[TypeAct],
+    value: &str,
+    depth: usize,
+    args: &ActArgs,
+) -> Option<String> {
+    // Members that can't be checked don't constrain the value further
+    let types_check: Vec<String> = types
+        .iter()
+        .filter_map(|intersection_member| gen_type_check(intersection_member, value, depth, args))
+        .collect();
+    if types_check.is_empty() {
+        return None;
+    }
+    Some(format!("({})", types_check.join(" && ")))
+}
+
+// Check that `check` holds for the elements of `items` bound to `binding`
+pub fn gen_every_check(items: &str, binding: &str, check: &str, args: &ActArgs) -> Option<String> {
+    match args.array_check {
+        ArrayCheckMode::Full => Some(format!("{}.every(({}) => {})", items, binding, check)),
+        ArrayCheckMode::Sample => Some(format!(
+            "{}.slice(0, {}).every(({}) => {})",
+            items, args.array_sample_size, binding, check
+        )),
+        ArrayCheckMode::Shape => None,
+    }
+}
+
+// Check the elements of `items` (an array expression) against `elem_type`
+pub fn gen_items_type_check(
+    elem_type: &TypeAct,
+    items: &str,
+    depth: usize,
+    args: &ActArgs,
+) -> Option<String> {
+    let item_name = format!("__act_item_{}", depth);
+    let elem_check = gen_type_check(elem_type, &item_name, depth + 1, args)?;
+    gen_every_check(items, &item_name, &elem_check, args)
+}
+
+pub fn gen_map_type_check(
+    key_type: &TypeAct,
+    value_type: &TypeAct,
+    value: &str,
+    depth: usize,
+    args: &ActArgs,
+) -> String {
+    let map_check = format!("{} instanceof Map", value);
+    if !args.deep_collections {
+        return map_check;
+    }
+    let key_name = format!("__act_key_{}", depth);
+    let value_name = format!("__act_value_{}", depth);
+    let entry_checks: Vec<String> = gen_type_check(key_type, &key_name, depth + 1, args)
+        .into_iter()
+        .chain(gen_type_check(value_type, &value_name, depth + 1, args))
+        .collect();
+    if entry_checks.is_empty() {
+        return map_check;
+    }
+    let entries_check = gen_every_check(
+        &format!("Array.from({})", value),
+        &format!("[{}, {}]", key_name, value_name),
+        &entry_checks.join(" && "),
+        args,
+    );
+    match entries_check {
+        Some(entries_check) => format!("({} && {})", map_check, entries_check),
+        None => map_check,
+    }
+}
+
+pub fn gen_set_type_check(
+    elem_type: &TypeAct,
+    value: &str,
+    depth: usize,
+    args: &ActArgs,
+) -> String {
+    let set_check = format!("{} instanceof Set", value);
+    if !args.deep_collections {
+        return set_check;
+    }
+    match gen_items_type_check(elem_type, &format!("Array.from({})", value), depth, args) {
+        Some(items_check) => format!("({} && {})", set_check, items_check),
+        None => set_check,
+    }
+}
+
+pub fn gen_array_type_check(
+    elem_type: &TypeAct,
+    value: &str,
+    depth: usize,
+    args: &ActArgs,
+) -> String {
+    let array_check = format!("Array.isArray({})", value);
+    match gen_items_type_check(elem_type, value, depth, args) {
+        Some(items_check) => format!("({} && {})", array_check, items_check),
+        None => array_check,
+    }
+}
+
+// Elements before the rest element, the type of the rest element and the elements after it
+pub fn split_tuple_elements(
+    elements: &[TupleElementAct],
+) -> (&[TupleElementAct], Option<&TypeAct>, &[TupleElementAct]) {
+    let rest_element = elements
+        .iter()
+        .enumerate()
+        .find_map(|(index, element)| match element {
+            TupleElementAct::Rest(rest_type) => Some((index, rest_type)),
+            _ => None,
+        });
+    match rest_element {
+        Some((rest_index, rest_type)) => (
+            &elements[..rest_index],
+            Some(rest_type),
+            &elements[rest_index + 1..],
+        ),
+        None => (elements, None, &[]),
+    }
+}
+
+// Length bounds of a tuple, None when it accepts any length
+pub fn gen_tuple_length_check(elements: &[TupleElementAct], value: &str) -> Option<String> {
+    let (_, rest_type, _) = split_tuple_elements(elements);
+    let min_len = elements
+        .iter()
+        .filter(|element| matches!(element, TupleElementAct::Required(_)))
+        .count();
+    match rest_type {
+        Some(_) if min_len > 0 => Some(format!("{}.length >= {}", value, min_len)),
+        Some(_) => None,
+        None if min_len == elements.len() => Some(format!("{}.length === {}", value, min_len)),
+        None => Some(format!(
+            "{}.length >= {} && {}.length <= {}",
+            value,
+            min_len,
+            value,
+            elements.len()
+        )),
+    }
+}
+
+pub fn gen_tuple_type_check(
+    elements: &[TupleElementAct],
+    value: &str,
+    depth: usize,
+    args: &ActArgs,
+) -> String {
+    let (leading_elements, rest_type, trailing_elements) = split_tuple_elements(elements);
+    let mut checks: Vec<String> = vec![format!("Array.isArray({})", value)];
+    checks.extend(gen_tuple_length_check(elements, value));
+    for (index, element) in leading_elements.iter().enumerate() {
+        let element_value = format!("{}[{}]", value, index);
+        match element {
+            TupleElementAct::Optional(act_type) => {
+                if let Some(element_check) = gen_type_check(act_type, &element_value, depth, args) {
+                    checks.push(format!(
+                        "({} === undefined || {})",
+                        element_value, element_check
+                    ))
+                }
+            }
+            TupleElementAct::Required(act_type) | TupleElementAct::Rest(act_type) => {
+                checks.extend(gen_type_check(act_type, &element_value, depth, args))
+            }
+        }
+    }
+    for (index, element) in trailing_elements.iter().enumerate() {
+        let element_value = format!(
+            "{}[{}.length - {}]",
+            value,
+            value,
+            trailing_elements.len() - index
+        );
+        if let TupleElementAct::Required(act_type) = element {
+            checks.extend(gen_type_check(act_type, &element_value, depth, args))
+        }
+    }
+    if let Some(rest_type) = rest_type {
+        let rest_index = leading_elements.len();
+        let rest_items = match trailing_elements.len() {
+            0 => format!("{}.slice({})", value, rest_index),
+            trailing_len => format!(
+                "{}.slice({}, {}.length - {})",
+                value, rest_index, value, trailing_len
+            ),
+        };
+        checks.extend(gen_items_type_check(rest_type, &rest_items, depth, args))
+    }
+    format!("({})", checks.join(" && "))
+}
+
+pub fn get_property_access(value: &str, property_name: &str) -> String {
+    let is_identifier = property_name.chars().enumerate().all(|(index, c)| {
+        c == '_' || c == '$' || c.is_ascii_alphabetic() || (index > 0 && c.is_ascii_digit())
+    });
+    if is_identifier && !property_name.is_empty() {
+        format!("{}.{}", value, property_name)
+    } else {
+        format!("{}[{:?}]", value, property_name)
+    }
+}
+
+pub fn gen_object_type_check(
+    properties: &[PropertyAct],
+    value: &str,
+    depth: usize,
+    args: &ActArgs,
+) -> String {
+    // An empty object type accepts every value but null and undefined
+    if properties.is_empty() {
+        return format!("({} !== null && {} !== undefined)", value, value);
+    }
+    let mut checks: Vec<String> = vec![
+        format!("typeof {} === 'object'", value),
+        format!("{} !== null", value),
+    ];
+    for property in properties {
+        let property_value = get_property_access(value, &property.name);
+        if let Some(property_check) =
+            gen_type_check(&property.act_type, &property_value, depth, args)
+        {
+            if property.optional {
+                checks.push(format!(
+                    "({} === undefined || {})",
+                    property_value, property_check
+                ))
+            } else {
+                checks.push(property_check)
+            }
+        }
+    }
+    format!("({})", checks.join(" && "))
+}
+
+pub fn gen_record_type_check(
+    value_type: &TypeAct,
+    value: &str,
+    depth: usize,
+    args: &ActArgs,
+) -> String {
+    let object_check = format!("typeof {} === 'object' && {} !== null", value, value);
+    match gen_items_type_check(
+        value_type,
+        &format!("Object.values({})", value),
+        depth,
+        args,
+    ) {
+        Some(values_check) => format!("({} && {})", object_check, values_check),
+        None => format!("({})", object_check),
+    }
+}
+
+pub fn escape_regexp(text: &str) -> String {
+    let mut escaped = String::new();
+    for character in text.chars() {
+        match character {
+            '\\' | '^' | '$' | '.' | '*' | '+' | '?' | '(' | ')' | '[' | ']' | '{' | '}' | '|'
+            | '/' => {
+                escaped.push('\\');
+                escaped.push(character);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+// Strings whose `+s` is a number, TypeScript accepts them for `${number}` when the number
+// is finite. Decimals too large for a double, like `1e400`, still match.
+const NUMBER_STRING_PATTERN: &str = r"(?:\s*(?:[+-]?(?:\d+\.?\d*|\.\d+)(?:[eE][+-]?\d+)?|0[xX][\da-fA-F]+|0[oO][0-7]+|0[bB][01]+)\s*|\s+)";
+
+// RegExp source matching the strings a template literal placeholder accepts
+pub fn gen_template_placeholder_pattern(act_type: &TypeAct) -> Option<String> {
+    match act_type {
+        TypeAct::String => Some(r"[\s\S]*".to_string()),
+        TypeAct::Number => Some(NUMBER_STRING_PATTERN.to_string()),
+        TypeAct::BigInt => Some(r"-?\d+".to_string()),
+        TypeAct::Boolean => Some("(?:true|false)".to_string()),
+        TypeAct::Null => Some("null".to_string()),
+        TypeAct::Undefined => Some("undefined".to_string()),
+        TypeAct::Literal(LiteralAct::String(text)) => Some(escape_regexp(text)),
+        TypeAct::Literal(literal) => Some(escape_regexp(&get_literal_string(literal))),
+        TypeAct::TemplateLiteral(parts) => gen_template_pattern(parts),
+        TypeAct::Union(types) => {
+            let mut patterns: Vec<String> = vec![];
+            for union_member in types {
+                patterns.push(gen_template_placeholder_pattern(union_member)?);
+            }
+            Some(format!("(?:{})", patterns.join("|")))
+        }
+        _ => None,
+    }
+}
+
+pub fn gen_template_pattern(parts: &[TemplatePartAct]) -> Option<String> {
+    let mut pattern = String::new();
+    for part in parts {
+        match part {
+            TemplatePartAct::Text(text) => pattern.push_str(&escape_regexp(text)),
+            TemplatePartAct::Placeholder(act_type) => {
+                pattern.push_str(&gen_template_placeholder_pattern(act_type)?)
+            }
+        }
+    }
+    Some(pattern)
+}
+
+pub fn gen_template_literal_type_check(parts: &[TemplatePartAct], value: &str) -> String {
+    match gen_template_pattern(parts) {
+        Some(pattern) => format!(
+            "(typeof {} === 'string' && /^{}$/.test({}))",
+            value, pattern, value
+        ),
+        // Placeholders we can't match still require a string
+        None => format!("typeof {} === 'string'", value),
+    }
+}
+
+pub fn gen_type_check(
+    act_type: &TypeAct,
+    value: &str,
+    depth: usize,
+    args: &ActArgs,
+) -> Option<String> {
+    match act_type {
+        TypeAct::Union(types) => return gen_union_type_check(types, value, depth, args),
+        TypeAct::Intersection(types) => {
+            return gen_intersection_type_check(types, value, depth, args)
+        }
+        TypeAct::Array(elem_type) => {
+            return Some(gen_array_type_check(elem_type, value, depth, args))
+        }
+        TypeAct::Tuple(elements) => {
+            return Some(gen_tuple_type_check(elements, value, depth, args))
+        }
+        TypeAct::Object(properties) => {
+            return Some(gen_object_type_check(properties, value, depth, args))
+        }
+        TypeAct::Record(value_type) => {
+            return Some(gen_record_type_check(value_type, value, depth, args))
+        }
+        TypeAct::TemplateLiteral(parts) => {
+            return Some(gen_template_literal_type_check(parts, value))
+        }
+        TypeAct::Map(key_type, value_type) => {
+            return Some(gen_map_type_check(key_type, value_type, value, depth, args))
+        }
+        TypeAct::Set(elem_type) => return Some(gen_set_type_check(elem_type, value, depth, args)),
+        // Promises resolve later so only the promise itself is checked
+        TypeAct::Promise(_) => return Some(format!("{} instanceof Promise", value)),
+        TypeAct::Void => return Some(format!("{} === undefined", value)),
+        // Reaching a `never` parameter is always a mistake
+        TypeAct::Never => return Some("false".to_string()),
+        TypeAct::NonPrimitive => {
+            return Some(format!(
+                "((typeof {} === 'object' && {} !== null) || typeof {} === 'function')",
+                value, value, value
+            ))
+        }
+        TypeAct::Function | TypeAct::Signature(_, _) => {
+            return Some(format!("typeof {} === 'function'", value))
+        }
+        TypeAct::Date if args.reject_invalid_dates => {
+            return Some(format!(
+                "({} instanceof Date && !isNaN({}.getTime()))",
+                value, value
+            ))
+        }
+        _ => {}
+    }
+    let ts_type = get_ts_type_from_acttype(act_type);
+    let typeinfo_operator = get_typeinfo_operator_from_acttype(act_type);
+    match typeinfo_operator.as_str() {
+        "instanceof" | "===" => Some(format!(r#"{} {} {}"#, value, typeinfo_operator, ts_type)),
+        "typeof" => Some(format!(
+            r#"{} {} === '{}'"#,
+            typeinfo_operator, value, ts_type
+        )),
+        _ => None,
+    }
+}
+
+pub fn escape_template_literal(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('`', "\\`")
+        .replace("${", "\\${")
+}
+
+// How a parameter is named in messages, destructured ones by their pattern
+pub fn get_param_label(param: &ParamAct) -> String {
+    let param_name = match &param.destructure {
+        Some(destructure) => destructure.pattern.to_string(),
+        None => param.name.to_string(),
+    };
+    if param.optional {
+        format!("optional {}", param_name)
+    } else {
+        param_name
+    }
+}
+
 pub fn gen_param_type_check_patch(
     param: ParamAct,
-    symbol_name: &String,
-    file_name: &String,
+    type_check: String,
+    symbol_name: &str,
+    file_name: &str,
+    args: &ActArgs,
 ) -> String {
-    let args = ActArgs::parse();
-    let patch_type = args.patch_type;
-    let param_ts_type = get_ts_type_from_acttype(&param.act_type);
-    let param_js_constructor = get_js_constructor_from_acttype(&param.act_type);
-    let log_message = format!(
-        r#"`[{}=>{}] {} isn't of type {} but of type ${{typeof {}}}`"#,
-        file_name, symbol_name, param.name, param_ts_type, param.name
-    );
+    let param_label = escape_template_literal(&get_param_label(&param));
+    gen_value_type_check_patch(
+        &param.name,
+        &param_label,
+        &param.act_type,
+        type_check,
+        symbol_name,
+        file_name,
+        args,
+    )
+}
+
+// `value_label` names the value in messages, it is inserted as is in a template literal
+pub fn gen_value_type_check_patch(
+    value: &str,
+    value_label: &str,
+    act_type: &TypeAct,
+    type_check: String,
+    symbol_name: &str,
+    file_name: &str,
+    args: &ActArgs,
+) -> String {
+    let patch_type = &args.patch_type;
+    let value_ts_type = get_ts_type_from_acttype(act_type);
+    let value_js_constructor = get_js_constructor_from_acttype(act_type);
+    let log_message = if is_value_set_acttype(act_type) {
+        // `String()` throws for objects without a usable `toString`, like `Object.create(null)`
+        format!(
+            r#"`[{}=>{}] {} isn't one of {} but is ${{typeof {} === 'string' ? JSON.stringify({}) : typeof {} === 'object' && {} !== null ? Object.prototype.toString.call({}) : String({})}}`"#,
+            file_name,
+            symbol_name,
+            value_label,
+            escape_template_literal(&value_ts_type),
+            value,
+            value,
+            value,
+            value,
+            value,
+            value
+        )
+    } else {
+        format!(
+            r#"`[{}=>{}] {} isn't of type {} but of type ${{{} === null ? "null" : typeof {}}}`"#,
+            file_name,
+            symbol_name,
+            value_label,
+            escape_template_literal(&value_ts_type),
+            value,
+            value
+        )
+    };
     let patch_body = match patch_type {
-        PatchType::Fix => format!(
+        PatchType::Fix if !value_js_constructor.is_empty() => format!(
             r#"console.warn({}," and was casted"); {}({});"#,
-            log_message, param_js_constructor, param.name
+            log_message, value_js_constructor, value
         ),
         PatchType::Error => format!(r#"throw new TypeError({});"#, log_message),
-        PatchType::Warning => format!(r#"console.warn({});"#, log_message),
-    };
-    let typeinfo_operator = get_typeinfo_operator_from_acttype(&param.act_type);
-
-    let conditional_string = match typeinfo_operator.as_str() {
-        "instanceof" => format!(
-            r#"!({} {} {})"#,
-            param.name, typeinfo_operator, param_ts_type
-        ),
-        "typeof" => format!(
-            r#"{} {} !== '{}'"#,
-            typeinfo_operator, param.name, param_ts_type
-        ),
-        _ => "true".to_string(),
+        PatchType::Warning | PatchType::Fix => format!(r#"console.warn({});"#, log_message),
     };
     let patch_string = format!(
         r#"
-    if({}){{
+    if(!({})){{
     {}
     }}
     "#,
-        conditional_string, patch_body
+        type_check, patch_body
     );
     patch_string
 }
 
+// Check the arguments from `start` to `length - end_offset` one by one to report the offending index
+pub fn gen_rest_items_check_patch(
+    param_name: &str,
+    elem_type: &TypeAct,
+    start: usize,
+    end_offset: usize,
+    symbol_name: &str,
+    file_name: &str,
+    args: &ActArgs,
+) -> Option<String> {
+    let item = format!("{}[__act_index]", param_name);
+    let item_check = gen_type_check(elem_type, &item, 0, args)?;
+    let item_label = format!("{}[${{__act_index}}]", param_name);
+    let end = match end_offset {
+        0 => format!("{}.length", param_name),
+        _ => format!("{}.length - {}", param_name, end_offset),
+    };
+    // Rest arguments are always an array, so there is nothing to check with `Shape`
+    let end = match args.array_check {
+        ArrayCheckMode::Full => end,
+        ArrayCheckMode::Sample => {
+            format!("Math.min({}, {})", end, start + args.array_sample_size)
+        }
+        ArrayCheckMode::Shape => return None,
+    };
+    Some(format!(
+        r#"
+    for (let __act_index = {}; __act_index < {}; __act_index++) {{{}}}
+    "#,
+        start,
+        end,
+        gen_value_type_check_patch(
+            &item,
+            &item_label,
+            elem_type,
+            item_check,
+            symbol_name,
+            file_name,
+            args
+        )
+    ))
+}
+
+pub fn gen_rest_tuple_check_patch(
+    param_name: &str,
+    elements: &[TupleElementAct],
+    symbol_name: &str,
+    file_name: &str,
+    args: &ActArgs,
+) -> String {
+    let (leading_elements, rest_type, trailing_elements) = split_tuple_elements(elements);
+    let mut patches: Vec<String> = vec![];
+    if let Some(length_check) = gen_tuple_length_check(elements, param_name) {
+        let length_message = format!(
+            r#"`[{}=>{}] {} isn't of type {} but has ${{{}.length}} elements`"#,
+            file_name,
+            symbol_name,
+            param_name,
+            escape_template_literal(&get_ts_type_from_acttype(&TypeAct::Tuple(
+                elements.to_vec()
+            ))),
+            param_name
+        );
+        patches.push(format!(
+            r#"
+    if(!({})){{
+    {}
+    }}
+    "#,
+            length_check,
+            gen_report_statement(&length_message, &args.patch_type)
+        ));
+    }
+    let mut positioned_elements: Vec<(String, String, &TupleElementAct)> = vec![];
+    for (index, element) in leading_elements.iter().enumerate() {
+        positioned_elements.push((
+            format!("{}[{}]", param_name, index),
+            format!("{}[{}]", param_name, index),
+            element,
+        ));
+    }
+    for (index, element) in trailing_elements.iter().enumerate() {
+        let offset = trailing_elements.len() - index;
+        positioned_elements.push((
+            format!("{}[{}.length - {}]", param_name, param_name, offset),
+            format!("{}[${{{}.length - {}}}]", param_name, param_name, offset),
+            element,
+        ));
+    }
+    for (item, item_label, element) in positioned_elements {
+        let (elem_type, optional) = match element {
+            TupleElementAct::Required(elem_type) => (elem_type, false),
+            TupleElementAct::Optional(elem_type) => (elem_type, true),
+            TupleElementAct::Rest(elem_type) => (elem_type, false),
+        };
+        if let Some(item_check) = gen_type_check(elem_type, &item, 0, args) {
+            let item_check = if optional {
+                format!("{} === undefined || {}", item, item_check)
+            } else {
+                item_check
+            };
+            patches.push(gen_value_type_check_patch(
+                &item,
+                &item_label,
+                elem_type,
+                item_check,
+                symbol_name,
+                file_name,
+                args,
+            ));
+        }
+    }
+    if let Some(rest_type) = rest_type {
+        patches.extend(gen_rest_items_check_patch(
+            param_name,
+            rest_type,
+            leading_elements.len(),
+            trailing_elements.len(),
+            symbol_name,
+            file_name,
+            args,
+        ));
+    }
+    patches.join("")
+}
+
+// Rest arguments are checked one by one so messages point at the offending index
+pub fn gen_rest_param_check_patch(
+    param: &ParamAct,
+    symbol_name: &str,
+    file_name: &str,
+    args: &ActArgs,
+) -> Option<String> {
+    match &param.act_type {
+        TypeAct::Array(elem_type) => {
+            gen_rest_items_check_patch(&param.name, elem_type, 0, 0, symbol_name, file_name, args)
+        }
+        TypeAct::Tuple(elements) => Some(gen_rest_tuple_check_patch(
+            &param.name,
+            elements,
+            symbol_name,
+            file_name,
+            args,
+        )),
+        _ => None,
+    }
+}
+
+// Values reported from within a callback can't be casted, so fix falls back to a warning
+pub fn gen_report_statement(log_message: &str, patch_type: &PatchType) -> String {
+    match patch_type {
+        PatchType::Error => format!(r#"throw new TypeError({});"#, log_message),
+        PatchType::Warning | PatchType::Fix => format!(r#"console.warn({});"#, log_message),
+    }
+}
+
+// Replace the callback with a function checking its arguments and return value on each call
+pub fn gen_callback_wrap_patch(
+    param_name: &str,
+    property: bool,
+    callback_params: &[TupleElementAct],
+    return_type: &TypeAct,
+    symbol_name: &str,
+    file_name: &str,
+    args: &ActArgs,
+) -> Option<String> {
+    let mut checks: Vec<String> = vec![];
+    let params_type = TypeAct::Tuple(callback_params.to_vec());
+    let params_check = match callback_params {
+        // Extra arguments are harmless to a callback declaring none
+        [] => None,
+        _ => gen_type_check(&params_type, "__act_args", 0, args),
+    };
+    if let Some(params_check) = params_check {
+        let log_message = format!(
+            r#"`[{}=>{}] {} was called with arguments that aren't of type {}`"#,
+            file_name,
+            symbol_name,
+            param_name,
+            escape_template_literal(&get_ts_type_from_acttype(&params_type))
+        );
+        checks.push(format!(
+            r#"
+    if(!({})){{
+    {}
+    }}"#,
+            params_check,
+            gen_report_statement(&log_message, &args.patch_type)
+        ));
+    }
+    checks.push(
+        r#"
+    const __act_result = __act_callback.apply(this, __act_args);"#
+            .to_string(),
+    );
+    // Callbacks typed to return void may return anything
+    let return_check = match return_type {
+        TypeAct::Void => None,
+        _ => gen_type_check(return_type, "__act_result", 0, args),
+    };
+    if let Some(return_check) = return_check {
+        let log_message = format!(
+            r#"`[{}=>{}] {} returned a value that isn't of type {} but of type ${{__act_result === null ? "null" : typeof __act_result}}`"#,
+            file_name,
+            symbol_name,
+            param_name,
+            escape_template_literal(&get_ts_type_from_acttype(return_type))
+        );
+        checks.push(format!(
+            r#"
+    if(!({})){{
+    {}
+    }}"#,
+            return_check,
+            gen_report_statement(&log_message, &args.patch_type)
+        ));
+    }
+    if checks.len() == 1 {
+        return None;
+    }
+    // The property is assigned before the body runs, it has to be wrapped too
+    let property_assignment = match property {
+        true => format!(
+            r#"
+    this.{} = {};"#,
+            param_name, param_name
+        ),
+        false => "".to_string(),
+    };
+    Some(format!(
+        r#"
+    if(typeof {} === 'function'){{
+    {} = ((__act_callback) => function (...__act_args) {{{}
+    return __act_result;
+    }})({});{}
+    }}
+    "#,
+        param_name,
+        param_name,
+        checks.join(""),
+        param_name,
+        property_assignment
+    ))
+}
+
 pub fn get_function_param_patch(
     param: ParamAct,
+    type_check: String,
     body_start: u32,
-    symbol_name: &String,
-    file_name: &String,
+    symbol_name: &str,
+    file_name: &str,
+    args: &ActArgs,
 ) -> PatchAct {
-    let patch_string = gen_param_type_check_patch(param, symbol_name, file_name);
-    return PatchAct {
+    let patch_string = gen_param_type_check_patch(param, type_check, symbol_name, file_name, args);
+    PatchAct {
         byte_pos: body_start,
         patch: patch_string.as_bytes().to_vec(),
-    };
+        replaced_len: 0,
+    }
 }
 
 pub fn get_function_params_patches(
     params: Vec<ParamAct>,
     body_start: u32,
+    checks_start: u32,
     symbol_name: String,
     file_name: String,
+    args: &ActArgs,
 ) -> Vec<PatchAct> {
     let mut params_patches: Vec<PatchAct> = vec![];
-    for param in params
-        .into_iter()
-        .filter(|x| x.act_type != TypeAct::Unknown)
-    {
-        params_patches.push(get_function_param_patch(
-            param,
-            body_start,
-            &symbol_name,
-            &file_name,
-        ));
+    let mut destructure_patches: Vec<PatchAct> = vec![];
+    for param in params {
+        let param = match (&param.act_type, &param.default_type) {
+            (TypeAct::Unknown, Some(default_type)) if args.infer_defaults => ParamAct {
+                act_type: default_type.clone(),
+                ..param
+            },
+            _ => param,
+        };
+        let param_label = get_param_label(&param);
+        if let TypeAct::Any(keyword) = &param.act_type {
+            println!(
+                "[{}=>{}] {} is intentionally unchecked: typed as {}",
+                file_name, symbol_name, param_label, keyword
+            );
+        }
+        for reason in get_unchecked_reasons(&param.act_type) {
+            println!(
+                "[{}=>{}] {} is not fully checked: {}",
+                file_name, symbol_name, param_label, reason
+            );
+        }
+        if param.rest {
+            if let Some(rest_patch) =
+                gen_rest_param_check_patch(&param, &symbol_name, &file_name, args)
+            {
+                params_patches.push(PatchAct {
+                    byte_pos: checks_start,
+                    patch: rest_patch.as_bytes().to_vec(),
+                    replaced_len: 0,
+                });
+            }
+            continue;
+        }
+        let callback_wrap_patch = match &param.act_type {
+            TypeAct::Signature(callback_params, return_type) if args.wrap_callbacks => {
+                gen_callback_wrap_patch(
+                    &param.name,
+                    param.property,
+                    callback_params,
+                    return_type,
+                    &symbol_name,
+                    &file_name,
+                    args,
+                )
+            }
+            _ => None,
+        };
+        // Types we can't check at runtime are skipped
+        if let Some(type_check) = gen_type_check(&param.act_type, &param.name, 0, args) {
+            // The pattern is only replaced by a temporary when there is something to check
+            if let Some(destructure) = &param.destructure {
+                params_patches.push(PatchAct {
+                    byte_pos: destructure.byte_pos,
+                    patch: param.name.as_bytes().to_vec(),
+                    replaced_len: destructure.pattern.len() as u32,
+                });
+                // `var` like parameters, so the body can still redeclare the bound names
+                destructure_patches.push(PatchAct {
+                    byte_pos: body_start,
+                    patch: format!(
+                        r#"
+        var {} = {};
+        "#,
+                        destructure.pattern, param.name
+                    )
+                    .as_bytes()
+                    .to_vec(),
+                    replaced_len: 0,
+                });
+            }
+            let type_check = if param.optional {
+                format!("{} === undefined || {}", param.name, type_check)
+            } else {
+                type_check
+            };
+            params_patches.push(get_function_param_patch(
+                param,
+                type_check,
+                checks_start,
+                &symbol_name,
+                &file_name,
+                args,
+            ));
+        }
+        if let Some(callback_wrap_patch) = callback_wrap_patch {
+            params_patches.push(PatchAct {
+                byte_pos: checks_start,
+                patch: callback_wrap_patch.as_bytes().to_vec(),
+                replaced_len: 0,
+            });
+        }
     }
+    // Arguments are destructured once they are all checked
+    params_patches.extend(destructure_patches);
     params_patches
 }
 
-pub fn apply_patches(patches: Vec<PatchAct>, file_path: PathBuf) -> Result<(), String> {
-    let mut buffer = fs::read(&file_path).unwrap_or_default();
+pub fn get_patched_buffer(mut buffer: Vec<u8>, patches: Vec<PatchAct>) -> Vec<u8> {
     let mut patch_index_helper = PatchIndexHelper::new();
     for patch in patches {
         let pos: usize = patch_index_helper.get_drifted_index(patch.byte_pos) as usize;
         let patch_len: u32 = patch.patch.len() as u32;
-        buffer.splice(pos..pos, patch.patch);
-        patch_index_helper.register_patched_index(patch.byte_pos, patch_len)
+        buffer.splice(pos..pos + patch.replaced_len as usize, patch.patch);
+        patch_index_helper.register_replaced_index(patch.byte_pos, patch_len, patch.replaced_len)
     }
+    buffer
+}
+
+pub fn apply_patches(patches: Vec<PatchAct>, file_path: PathBuf) -> Result<(), String> {
+    let buffer = get_patched_buffer(fs::read(&file_path).unwrap_or_default(), patches);
     let args = ActArgs::parse();
     let out_folder_path = args.out_folder_path;
     let in_folder_path = args.folder_path;
@@ -142,3 +943,405 @@ pub fn apply_patches(patches: Vec<PatchAct>, file_path: PathBuf) -> Result<(), S
     fs::write(patched_file_path, buffer).unwrap();
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::act_structs::DestructureAct;
+
+    fn default_args() -> ActArgs {
+        ActArgs::parse_from(["actypes"])
+    }
+
+    #[test]
+    fn union_type_check_test() {
+        let args = default_args();
+        let act_type = TypeAct::Union(vec![TypeAct::Number, TypeAct::Undefined, TypeAct::Null]);
+        assert_eq!(
+            gen_type_check(&act_type, "a", 0, &args).unwrap(),
+            "(typeof a === 'number' || typeof a === 'undefined' || a === null)"
+        );
+        let act_type = TypeAct::Union(vec![TypeAct::String, TypeAct::Unknown]);
+        assert_eq!(gen_type_check(&act_type, "a", 0, &args), None);
+    }
+
+    #[test]
+    fn literal_type_check_test() {
+        let args = default_args();
+        let act_type = TypeAct::Union(vec![
+            TypeAct::Literal(LiteralAct::String("asc".to_string())),
+            TypeAct::Literal(LiteralAct::Number(-1.0)),
+            TypeAct::Literal(LiteralAct::BigInt("2".to_string())),
+            TypeAct::Literal(LiteralAct::Boolean(true)),
+        ]);
+        assert_eq!(
+            gen_type_check(&act_type, "a", 0, &args).unwrap(),
+            r#"(a === "asc" || a === -1 || a === 2n || a === true)"#
+        );
+        assert_eq!(escape_template_literal(r#""`${x}`""#), r#""\`\${x}\`""#);
+    }
+
+    #[test]
+    fn array_type_check_test() {
+        let act_type = TypeAct::Array(Box::new(TypeAct::Array(Box::new(TypeAct::Number))));
+        assert_eq!(
+            gen_type_check(&act_type, "a", 0, &default_args()).unwrap(),
+            "(Array.isArray(a) && a.every((__act_item_0) => (Array.isArray(__act_item_0) && __act_item_0.every((__act_item_1) => typeof __act_item_1 === 'number'))))"
+        );
+        let args = ActArgs::parse_from([
+            "actypes",
+            "--array-check",
+            "sample",
+            "--array-sample-size",
+            "3",
+        ]);
+        assert_eq!(
+            gen_type_check(&act_type, "a", 0, &args).unwrap(),
+            "(Array.isArray(a) && a.slice(0, 3).every((__act_item_0) => (Array.isArray(__act_item_0) && __act_item_0.slice(0, 3).every((__act_item_1) => typeof __act_item_1 === 'number'))))"
+        );
+        let args = ActArgs::parse_from(["actypes", "--array-check", "shape"]);
+        assert_eq!(
+            gen_type_check(&act_type, "a", 0, &args).unwrap(),
+            "Array.isArray(a)"
+        );
+    }
+
+    #[test]
+    fn tuple_type_check_test() {
+        let args = default_args();
+        let act_type = TypeAct::Tuple(vec![
+            TupleElementAct::Required(TypeAct::String),
+            TupleElementAct::Optional(TypeAct::Number),
+        ]);
+        assert_eq!(
+            gen_type_check(&act_type, "a", 0, &args).unwrap(),
+            "(Array.isArray(a) && a.length >= 1 && a.length <= 2 && typeof a[0] === 'string' && (a[1] === undefined || typeof a[1] === 'number'))"
+        );
+        let act_type = TypeAct::Tuple(vec![
+            TupleElementAct::Required(TypeAct::String),
+            TupleElementAct::Rest(TypeAct::Number),
+            TupleElementAct::Required(TypeAct::Boolean),
+        ]);
+        assert_eq!(
+            gen_type_check(&act_type, "a", 0, &args).unwrap(),
+            "(Array.isArray(a) && a.length >= 2 && typeof a[0] === 'string' && typeof a[a.length - 1] === 'boolean' && a.slice(1, a.length - 1).every((__act_item_0) => typeof __act_item_0 === 'number'))"
+        );
+    }
+
+    #[test]
+    fn object_type_check_test() {
+        let act_type = TypeAct::Object(vec![
+            PropertyAct {
+                name: "id".to_string(),
+                act_type: TypeAct::String,
+                optional: false,
+            },
+            PropertyAct {
+                name: "item-count".to_string(),
+                act_type: TypeAct::Number,
+                optional: true,
+            },
+        ]);
+        assert_eq!(
+            gen_type_check(&act_type, "a", 0, &default_args()).unwrap(),
+            r#"(typeof a === 'object' && a !== null && typeof a.id === 'string' && (a["item-count"] === undefined || typeof a["item-count"] === 'number'))"#
+        );
+    }
+
+    #[test]
+    fn builtin_class_type_check_test() {
+        let map_type = TypeAct::Map(Box::new(TypeAct::String), Box::new(TypeAct::Number));
+        let set_type = TypeAct::Set(Box::new(TypeAct::Unknown));
+        let args = default_args();
+        assert_eq!(
+            gen_type_check(&map_type, "a", 0, &args).unwrap(),
+            "a instanceof Map"
+        );
+        assert_eq!(
+            gen_type_check(&TypeAct::Date, "a", 0, &args).unwrap(),
+            "a instanceof Date"
+        );
+        let args = ActArgs::parse_from(["actypes", "--deep-collections", "--reject-invalid-dates"]);
+        assert_eq!(
+            gen_type_check(&map_type, "a", 0, &args).unwrap(),
+            "(a instanceof Map && Array.from(a).every(([__act_key_0, __act_value_0]) => typeof __act_key_0 === 'string' && typeof __act_value_0 === 'number'))"
+        );
+        assert_eq!(
+            gen_type_check(&set_type, "a", 0, &args).unwrap(),
+            "a instanceof Set"
+        );
+        assert_eq!(
+            gen_type_check(&TypeAct::Date, "a", 0, &args).unwrap(),
+            "(a instanceof Date && !isNaN(a.getTime()))"
+        );
+    }
+
+    #[test]
+    fn keyword_type_check_test() {
+        let args = default_args();
+        assert_eq!(
+            gen_type_check(&TypeAct::Void, "a", 0, &args).unwrap(),
+            "a === undefined"
+        );
+        assert_eq!(
+            gen_type_check(&TypeAct::Never, "a", 0, &args).unwrap(),
+            "false"
+        );
+        assert_eq!(
+            gen_type_check(&TypeAct::NonPrimitive, "a", 0, &args).unwrap(),
+            "((typeof a === 'object' && a !== null) || typeof a === 'function')"
+        );
+        assert_eq!(
+            gen_type_check(&TypeAct::Function, "a", 0, &args).unwrap(),
+            "typeof a === 'function'"
+        );
+        assert_eq!(
+            gen_type_check(&TypeAct::Any("any".to_string()), "a", 0, &args),
+            None
+        );
+    }
+
+    #[test]
+    fn callback_wrap_patch_test() {
+        let args = default_args();
+        let callback_params = vec![TupleElementAct::Required(TypeAct::Number)];
+        assert_eq!(
+            gen_callback_wrap_patch("cb", false, &[], &TypeAct::Void, "test", "a.ts", &args),
+            None
+        );
+        let patch = gen_callback_wrap_patch(
+            "cb",
+            false,
+            &callback_params,
+            &TypeAct::String,
+            "test",
+            "a.ts",
+            &args,
+        )
+        .unwrap();
+        assert!(patch.contains("cb = ((__act_callback) => function (...__act_args) {"));
+        assert!(patch.contains(
+            "if(!((Array.isArray(__act_args) && __act_args.length === 1 && typeof __act_args[0] === 'number'))){"
+        ));
+        assert!(patch.contains("if(!(typeof __act_result === 'string')){"));
+        assert!(patch.contains("})(cb);"));
+        assert!(!patch.contains("this.cb"));
+        // A parameter property is stored before the wrapper is created
+        let patch = gen_callback_wrap_patch(
+            "cb",
+            true,
+            &callback_params,
+            &TypeAct::String,
+            "test",
+            "a.ts",
+            &args,
+        )
+        .unwrap();
+        assert!(patch.contains("})(cb);\n    this.cb = cb;"));
+    }
+
+    #[test]
+    fn record_type_check_test() {
+        let args = default_args();
+        assert_eq!(
+            gen_type_check(&TypeAct::Record(Box::new(TypeAct::Number)), "a", 0, &args).unwrap(),
+            "(typeof a === 'object' && a !== null && Object.values(a).every((__act_item_0) => typeof __act_item_0 === 'number'))"
+        );
+        assert_eq!(
+            gen_type_check(&TypeAct::Record(Box::new(TypeAct::Unknown)), "a", 0, &args).unwrap(),
+            "(typeof a === 'object' && a !== null)"
+        );
+    }
+
+    #[test]
+    fn template_literal_type_check_test() {
+        let args = default_args();
+        let prefixed_type = TypeAct::TemplateLiteral(vec![
+            TemplatePartAct::Text("user.".to_string()),
+            TemplatePartAct::Placeholder(TypeAct::String),
+        ]);
+        let unit_type = TypeAct::TemplateLiteral(vec![
+            TemplatePartAct::Placeholder(TypeAct::Number),
+            TemplatePartAct::Placeholder(TypeAct::Union(vec![
+                TypeAct::Literal(LiteralAct::String("px".to_string())),
+                TypeAct::Literal(LiteralAct::String("em".to_string())),
+            ])),
+        ]);
+        assert_eq!(
+            gen_type_check(&prefixed_type, "a", 0, &args).unwrap(),
+            r"(typeof a === 'string' && /^user\.[\s\S]*$/.test(a))"
+        );
+        assert_eq!(
+            gen_type_check(&unit_type, "a", 0, &args).unwrap(),
+            format!(
+                r"(typeof a === 'string' && /^{}(?:px|em)$/.test(a))",
+                NUMBER_STRING_PATTERN
+            )
+        );
+    }
+
+    #[test]
+    fn intersection_type_check_test() {
+        let args = default_args();
+        let act_type = TypeAct::Intersection(vec![
+            TypeAct::Array(Box::new(TypeAct::Unknown)),
+            TypeAct::Object(vec![PropertyAct {
+                name: "length".to_string(),
+                act_type: TypeAct::Literal(LiteralAct::Number(2.0)),
+                optional: false,
+            }]),
+        ]);
+        assert_eq!(
+            gen_type_check(&act_type, "a", 0, &args).unwrap(),
+            "(Array.isArray(a) && (typeof a === 'object' && a !== null && a.length === 2))"
+        );
+        let act_type =
+            TypeAct::Intersection(vec![TypeAct::Unknown, TypeAct::Any("any".to_string())]);
+        assert_eq!(gen_type_check(&act_type, "a", 0, &args), None);
+    }
+
+    #[test]
+    fn rest_param_check_patch_test() {
+        let args = default_args();
+        let patch =
+            gen_rest_items_check_patch("values", &TypeAct::Number, 0, 0, "test", "a.ts", &args)
+                .unwrap();
+        assert!(patch
+            .contains("for (let __act_index = 0; __act_index < values.length; __act_index++) {"));
+        assert!(patch.contains("if(!(typeof values[__act_index] === 'number')){"));
+        assert!(patch.contains("values[${__act_index}] isn't of type number"));
+        let sample_args = ActArgs::parse_from(["actypes", "--array-check", "sample"]);
+        let patch = gen_rest_items_check_patch(
+            "values",
+            &TypeAct::Number,
+            1,
+            0,
+            "test",
+            "a.ts",
+            &sample_args,
+        )
+        .unwrap();
+        assert!(patch.contains(
+            "for (let __act_index = 1; __act_index < Math.min(values.length, 11); __act_index++) {"
+        ));
+        let shape_args = ActArgs::parse_from(["actypes", "--array-check", "shape"]);
+        assert_eq!(
+            gen_rest_items_check_patch(
+                "values",
+                &TypeAct::Number,
+                0,
+                0,
+                "test",
+                "a.ts",
+                &shape_args
+            ),
+            None
+        );
+        let patch = gen_rest_tuple_check_patch(
+            "values",
+            &[
+                TupleElementAct::Required(TypeAct::String),
+                TupleElementAct::Optional(TypeAct::Boolean),
+            ],
+            "test",
+            "a.ts",
+            &args,
+        );
+        assert!(patch.contains("if(!(values.length >= 1 && values.length <= 2)){"));
+        assert!(patch.contains("if(!(typeof values[0] === 'string')){"));
+        assert!(patch.contains("if(!(values[1] === undefined || typeof values[1] === 'boolean')){"));
+        // Any length is accepted with no required element
+        let patch = gen_rest_tuple_check_patch(
+            "values",
+            &[
+                TupleElementAct::Optional(TypeAct::String),
+                TupleElementAct::Rest(TypeAct::Number),
+            ],
+            "test",
+            "a.ts",
+            &args,
+        );
+        assert!(!patch.contains("values.length >="));
+        assert!(patch.contains("for (let __act_index = 1; __act_index < values.length;"));
+    }
+
+    fn gen_fix_patch(name: &str, act_type: TypeAct) -> String {
+        let args = ActArgs::parse_from(["actypes", "fix"]);
+        let type_check = gen_type_check(&act_type, name, 0, &args).unwrap();
+        let param = ParamAct {
+            name: name.to_string(),
+            act_type,
+            optional: false,
+            rest: false,
+            default_type: None,
+            destructure: None,
+            property: false,
+        };
+        gen_param_type_check_patch(param, type_check, "test", "a.ts", &args)
+    }
+
+    #[test]
+    fn literal_fix_patch_test() {
+        let patch = gen_fix_patch("c", TypeAct::Literal(LiteralAct::Boolean(true)));
+        assert!(!patch.contains("===(c)"));
+        assert!(patch.contains("console.warn(`[a.ts=>test] c isn't one of true but is"));
+        // Objects are formatted without calling their own `toString`
+        assert!(patch.contains(
+            "typeof c === 'object' && c !== null ? Object.prototype.toString.call(c) : String(c)"
+        ));
+        let patch = gen_fix_patch(
+            "d",
+            TypeAct::Union(vec![
+                TypeAct::Literal(LiteralAct::String("asc".to_string())),
+                TypeAct::Literal(LiteralAct::String("desc".to_string())),
+            ]),
+        );
+        assert!(!patch.contains("===(d)"));
+        assert!(
+            patch.contains(r#"console.warn(`[a.ts=>test] d isn't one of "asc" | "desc" but is"#)
+        );
+        // Types with a constructor are still casted
+        let patch = gen_fix_patch("n", TypeAct::Number);
+        assert!(patch.contains(r#" and was casted"); Number(n);"#));
+    }
+
+    #[test]
+    fn destructured_param_patches_test() {
+        let args = default_args();
+        let get_patches = |act_type: TypeAct| {
+            let param = ParamAct {
+                name: "__act_param_0".to_string(),
+                act_type,
+                optional: false,
+                rest: false,
+                default_type: None,
+                destructure: Some(DestructureAct {
+                    pattern: "{ z }".to_string(),
+                    byte_pos: 11,
+                }),
+                property: false,
+            };
+            get_function_params_patches(
+                vec![param],
+                20,
+                20,
+                "test".to_string(),
+                "a.ts".to_string(),
+                &args,
+            )
+        };
+        // Nothing to check, the pattern is left as is
+        assert!(get_patches(TypeAct::Unknown).is_empty());
+        assert!(get_patches(TypeAct::Any("any".to_string())).is_empty());
+        let patches = get_patches(TypeAct::Object(vec![PropertyAct {
+            name: "z".to_string(),
+            act_type: TypeAct::Number,
+            optional: false,
+        }]));
+        assert_eq!(patches.len(), 3);
+        assert_eq!(patches[0].byte_pos, 11);
+        assert_eq!(patches[0].replaced_len, 5);
+        assert_eq!(patches[0].patch, b"__act_param_0");
+        assert!(String::from_utf8_lossy(&patches[2].patch).contains("var { z } = __act_param_0;"));
+    }
+}
@@ -1,28 +1,51 @@
+use std::collections::HashMap;
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::{println, vec};
 
-use swc_common::{sync::Lrc, Span};
+use swc_common::{sync::Lrc, Span, Spanned};
 use swc_common::{BytePos, SourceMap, SyntaxContext};
 use swc_ecma_ast::{
-    ArrowExpr, ClassDecl, Decl, EsVersion, FnDecl, FnExpr, Function, ModuleItem, Param, Pat,
-    TsKeywordType, TsKeywordTypeKind, TsType, VarDecl,
+    ArrowExpr, BinaryOp, BlockStmt, BlockStmtOrExpr, Callee, Class, ClassDecl, ClassExpr,
+    ClassMember, ClassProp, Decl, EsVersion, Expr, FnDecl, FnExpr, Function, KeyValueProp, Lit,
+    MemberProp, MethodProp, Module, ModuleItem, ObjectPatProp, Param, ParamOrTsParamProp, Pat,
+    PrivateProp, Prop, PropName, PropOrSpread, SetterProp, Stmt, TsCallSignatureDecl, TsEntityName,
+    TsEnumDecl, TsFnOrConstructorType, TsFnParam, TsInterfaceDecl, TsKeywordType,
+    TsKeywordTypeKind, TsLit, TsLitType, TsParamPropParam, TsTplLitType, TsTupleElement, TsType,
+    TsTypeAliasDecl, TsTypeAnn, TsTypeElement, TsTypeOperatorOp, TsTypeParamDecl,
+    TsTypeParamInstantiation, TsUnionOrIntersectionType, UnaryOp, VarDeclarator,
 };
 use swc_ecma_parser::{lexer::Lexer, Parser, StringInput, Syntax, TsConfig};
+use swc_ecma_visit::{Visit, VisitWith};
 
+use crate::act_scope::{
+    get_file_types_act, normalize_path, ActScope, ProjectTypesAct, TypeDeclAct,
+};
 use crate::act_structs::get_acttype_from_string;
+use crate::args_parser::ActArgs;
 use crate::{
     act_patch::{apply_patches, get_function_params_patches},
-    act_structs::{ClassAct, FunctionAct, MethodAct, ParamAct, PatchAct, TypeAct},
+    act_structs::{
+        ClassAct, DestructureAct, FunctionAct, LiteralAct, MethodAct, ParamAct, PatchAct,
+        PropertyAct, TemplatePartAct, TupleElementAct, TypeAct,
+    },
 };
+use clap::Parser as _;
 
 pub fn get_typeact_from_typeid(typeid: TsKeywordTypeKind) -> TypeAct {
     match typeid {
         TsKeywordTypeKind::TsBooleanKeyword => TypeAct::Boolean,
         TsKeywordTypeKind::TsNumberKeyword => TypeAct::Number,
         TsKeywordTypeKind::TsStringKeyword => TypeAct::String,
-        TsKeywordTypeKind::TsUnknownKeyword => TypeAct::Unknown,
+        TsKeywordTypeKind::TsUnknownKeyword => TypeAct::Any("unknown".to_string()),
+        TsKeywordTypeKind::TsAnyKeyword => TypeAct::Any("any".to_string()),
         TsKeywordTypeKind::TsBigIntKeyword => TypeAct::BigInt,
         TsKeywordTypeKind::TsSymbolKeyword => TypeAct::Symbol,
+        TsKeywordTypeKind::TsNullKeyword => TypeAct::Null,
+        TsKeywordTypeKind::TsUndefinedKeyword => TypeAct::Undefined,
+        TsKeywordTypeKind::TsVoidKeyword => TypeAct::Void,
+        TsKeywordTypeKind::TsNeverKeyword => TypeAct::Never,
+        TsKeywordTypeKind::TsObjectKeyword => TypeAct::NonPrimitive,
         _ => TypeAct::Unknown,
     }
 }
@@ -40,15 +63,30 @@ pub fn get_param_type_ann(param_pat: &Pat) -> Result<Box<TsType>, String> {
     }));
     if param_pat.is_ident() {
         let param_ident = param_pat.ident().unwrap();
-        if param_ident.optional {
-            // If is optionnal, we skip it
-            return Ok(param_type_ann);
-        }
         if param_ident.type_ann.is_none() {
             return Err(String::from("param_ident.type_ann.is_none()"));
         }
         let param_type_ann_wraped = param_ident.type_ann.unwrap();
         param_type_ann = param_type_ann_wraped.type_ann;
+    } else if param_pat.is_rest() {
+        let rest_pat = param_pat.rest().unwrap();
+        match rest_pat.type_ann {
+            Some(rest_type_ann) => param_type_ann = rest_type_ann.type_ann,
+            None => return Err(String::from("rest_pat.type_ann.is_none()")),
+        }
+    } else if param_pat.is_assign() {
+        // The annotation of `a: T = value` is on the left side
+        return get_param_type_ann(&param_pat.assign().unwrap().left);
+    } else if param_pat.is_object() || param_pat.is_array() {
+        let destructure_type_ann = match param_pat {
+            Pat::Object(object_pat) => object_pat.type_ann,
+            Pat::Array(array_pat) => array_pat.type_ann,
+            _ => None,
+        };
+        match destructure_type_ann {
+            Some(destructure_type_ann) => param_type_ann = destructure_type_ann.type_ann,
+            None => return Err(String::from("destructure_type_ann.is_none()")),
+        }
     } else if param_pat.is_expr() {
         let _param_expr = param_pat.expr().unwrap();
         // TODO:
@@ -56,200 +94,1103 @@ pub fn get_param_type_ann(param_pat: &Pat) -> Result<Box<TsType>, String> {
 
     Ok(param_type_ann)
 }
-pub fn get_param_type_act(param_pat: &Pat) -> TypeAct {
-    let param_type_ann = get_param_type_ann(param_pat).unwrap();
-    if param_type_ann.is_ts_keyword_type() {
-        get_typeact_from_typeid(param_type_ann.ts_keyword_type().unwrap().kind)
-    } else if param_type_ann.is_ts_type_ref() {
-        let type_ref = param_type_ann.ts_type_ref().unwrap();
-        if type_ref.type_name.is_ident() {
-            let type_ref_type_name = type_ref.type_name.ident().unwrap().sym.to_string();
-            get_acttype_from_string(&type_ref_type_name)
-        } else {
-            TypeAct::Unknown
+pub fn get_literal_act(ts_lit: &TsLit) -> Option<LiteralAct> {
+    match ts_lit {
+        TsLit::Str(str_lit) => Some(LiteralAct::String(str_lit.value.to_string())),
+        TsLit::Number(number_lit) => Some(LiteralAct::Number(number_lit.value)),
+        TsLit::BigInt(bigint_lit) => Some(LiteralAct::BigInt(bigint_lit.value.to_string())),
+        TsLit::Bool(bool_lit) => Some(LiteralAct::Boolean(bool_lit.value)),
+        TsLit::Tpl(_) => None,
+    }
+}
+
+pub fn get_template_typeact(tpl_lit_type: &TsTplLitType, scope: &ActScope) -> TypeAct {
+    let mut parts: Vec<TemplatePartAct> = vec![];
+    for (index, quasi) in tpl_lit_type.quasis.iter().enumerate() {
+        let text = match &quasi.cooked {
+            Some(cooked) => cooked.to_string(),
+            None => quasi.raw.to_string(),
+        };
+        if !text.is_empty() {
+            parts.push(TemplatePartAct::Text(text));
+        }
+        if let Some(placeholder_type) = tpl_lit_type.types.get(index) {
+            parts.push(TemplatePartAct::Placeholder(get_typeact_from_ts_type(
+                placeholder_type,
+                scope,
+            )));
+        }
+    }
+    match parts.as_slice() {
+        [] => TypeAct::Literal(LiteralAct::String("".to_string())),
+        [TemplatePartAct::Text(text)] => TypeAct::Literal(LiteralAct::String(text.to_string())),
+        _ => TypeAct::TemplateLiteral(parts),
+    }
+}
+
+pub fn get_property_name(key: &Expr) -> Option<String> {
+    match key {
+        Expr::Ident(ident) => Some(ident.sym.to_string()),
+        Expr::Lit(Lit::Str(str_lit)) => Some(str_lit.value.to_string()),
+        Expr::Lit(Lit::Num(number_lit)) => Some(number_lit.value.to_string()),
+        _ => None,
+    }
+}
+
+pub fn get_properties_act(members: &[TsTypeElement], scope: &ActScope) -> Vec<PropertyAct> {
+    let mut properties_act: Vec<PropertyAct> = vec![];
+    for member in members {
+        if let TsTypeElement::TsPropertySignature(property) = member {
+            if property.computed {
+                continue;
+            }
+            let property_name = match get_property_name(&property.key) {
+                Some(property_name) => property_name,
+                None => continue,
+            };
+            let property_type = match &property.type_ann {
+                Some(type_ann) => get_typeact_from_ts_type(&type_ann.type_ann, scope),
+                None => TypeAct::Unknown,
+            };
+            properties_act.push(PropertyAct {
+                name: property_name,
+                act_type: property_type,
+                optional: property.optional,
+            })
+        }
+    }
+    properties_act
+}
+
+pub fn get_fn_param_element_act(fn_param: &TsFnParam, scope: &ActScope) -> Option<TupleElementAct> {
+    let (type_ann, optional) = match fn_param {
+        // `this` only types the receiver of the call
+        TsFnParam::Ident(ident) if &*ident.id.sym == "this" => return None,
+        TsFnParam::Ident(ident) => (&ident.type_ann, ident.id.optional),
+        TsFnParam::Array(array_pat) => (&array_pat.type_ann, array_pat.optional),
+        TsFnParam::Object(object_pat) => (&object_pat.type_ann, object_pat.optional),
+        TsFnParam::Rest(rest_pat) => {
+            return Some(TupleElementAct::Rest(match &rest_pat.type_ann {
+                Some(type_ann) => get_rest_elem_typeact(&type_ann.type_ann, scope),
+                None => TypeAct::Unknown,
+            }))
         }
+    };
+    let param_type = match type_ann {
+        Some(type_ann) => get_typeact_from_ts_type(&type_ann.type_ann, scope),
+        None => TypeAct::Unknown,
+    };
+    if optional {
+        Some(TupleElementAct::Optional(param_type))
     } else {
-        TypeAct::Unknown
+        Some(TupleElementAct::Required(param_type))
+    }
+}
+
+pub fn get_signature_typeact(
+    type_params: Option<&TsTypeParamDecl>,
+    params: &[TsFnParam],
+    return_type_ann: Option<&TsTypeAnn>,
+    scope: &ActScope,
+) -> TypeAct {
+    let scope = &scope.with_type_params(get_type_params_act(type_params, None, scope));
+    let params_act = params
+        .iter()
+        .filter_map(|fn_param| get_fn_param_element_act(fn_param, scope))
+        .collect();
+    let return_type = match return_type_ann {
+        Some(return_type_ann) => get_typeact_from_ts_type(&return_type_ann.type_ann, scope),
+        None => TypeAct::Unknown,
+    };
+    TypeAct::Signature(params_act, Box::new(return_type))
+}
+
+pub fn is_signature_member(member: &TsTypeElement) -> bool {
+    matches!(
+        member,
+        TsTypeElement::TsCallSignatureDecl(_) | TsTypeElement::TsConstructSignatureDecl(_)
+    )
+}
+
+// Types with a call signature are functions, their properties aren't checked
+pub fn get_type_members_typeact(members: &[TsTypeElement], scope: &ActScope) -> TypeAct {
+    if !members.iter().any(is_signature_member) {
+        let properties = get_properties_act(members, scope);
+        // Without properties, an index signature types every value of the object
+        let index_signature = members.iter().find_map(|member| match member {
+            TsTypeElement::TsIndexSignature(index_signature) => Some(index_signature),
+            _ => None,
+        });
+        return match index_signature {
+            Some(index_signature) if properties.is_empty() => {
+                TypeAct::Record(Box::new(match &index_signature.type_ann {
+                    Some(type_ann) => get_typeact_from_ts_type(&type_ann.type_ann, scope),
+                    None => TypeAct::Unknown,
+                }))
+            }
+            _ => TypeAct::Object(properties),
+        };
+    }
+    let call_signatures: Vec<&TsCallSignatureDecl> = members
+        .iter()
+        .filter_map(|member| match member {
+            TsTypeElement::TsCallSignatureDecl(call_signature) => Some(call_signature),
+            _ => None,
+        })
+        .collect();
+    match call_signatures.as_slice() {
+        [call_signature] => get_signature_typeact(
+            call_signature.type_params.as_deref(),
+            &call_signature.params,
+            call_signature.type_ann.as_deref(),
+            scope,
+        ),
+        // Overloads and constructors can't be told apart at runtime
+        _ => TypeAct::Function,
     }
 }
 
-fn get_param_name(param_pat: Pat) -> String {
+pub fn get_rest_elem_typeact(rest_type: &TsType, scope: &ActScope) -> TypeAct {
+    match get_typeact_from_ts_type(rest_type, scope) {
+        TypeAct::Array(elem_type) => *elem_type,
+        _ => TypeAct::Unknown,
+    }
+}
+
+pub fn get_tuple_element_act(tuple_element: &TsTupleElement, scope: &ActScope) -> TupleElementAct {
+    match &tuple_element.label {
+        // Named members carry their modifiers on the label
+        Some(Pat::Rest(_)) => {
+            TupleElementAct::Rest(get_rest_elem_typeact(&tuple_element.ty, scope))
+        }
+        Some(Pat::Ident(label)) if label.optional => {
+            TupleElementAct::Optional(get_typeact_from_ts_type(&tuple_element.ty, scope))
+        }
+        _ => match &*tuple_element.ty {
+            TsType::TsOptionalType(optional_type) => {
+                TupleElementAct::Optional(get_typeact_from_ts_type(&optional_type.type_ann, scope))
+            }
+            TsType::TsRestType(rest_type) => {
+                TupleElementAct::Rest(get_rest_elem_typeact(&rest_type.type_ann, scope))
+            }
+            ts_type => TupleElementAct::Required(get_typeact_from_ts_type(ts_type, scope)),
+        },
+    }
+}
+
+// Bind type parameters to the type they are checked as. Parameters of an instantiated
+// declaration take their type argument, parameters of a function (`type_args` is None)
+// are inferred at each call so only their constraint can be checked.
+pub fn get_type_params_act(
+    type_param_decl: Option<&TsTypeParamDecl>,
+    type_args: Option<&[TypeAct]>,
+    scope: &ActScope,
+) -> HashMap<String, TypeAct> {
+    let mut type_params_act: HashMap<String, TypeAct> = HashMap::new();
+    let type_params = match type_param_decl {
+        Some(type_param_decl) => &type_param_decl.params,
+        None => return type_params_act,
+    };
+    for (index, type_param) in type_params.iter().enumerate() {
+        let type_param_name = type_param.name.sym.to_string();
+        // Constraints and defaults can refer to the previous type parameters
+        let type_param_scope = scope.with_type_params(type_params_act.clone());
+        let type_arg = type_args.and_then(|type_args| type_args.get(index));
+        let type_param_act = match (type_arg, &type_param.default, &type_param.constraint) {
+            (Some(type_arg), _, _) => type_arg.clone(),
+            (None, Some(default), _) if type_args.is_some() => {
+                get_typeact_from_ts_type(default, &type_param_scope)
+            }
+            (None, _, Some(constraint)) => get_typeact_from_ts_type(constraint, &type_param_scope),
+            (None, _, None) => TypeAct::Unchecked(format!(
+                "type parameter {} has no constraint",
+                type_param_name
+            )),
+        };
+        type_params_act.insert(type_param_name, type_param_act);
+    }
+    type_params_act
+}
+
+pub fn get_type_alias_typeact(
+    type_alias_decl: &TsTypeAliasDecl,
+    type_args: &[TypeAct],
+    scope: &ActScope,
+) -> TypeAct {
+    let type_alias_name = type_alias_decl.id.sym.to_string();
+    if let Some(cycle) = scope.get_cycle(&type_alias_name) {
+        return TypeAct::Unchecked(format!("circular type alias {}", cycle));
+    }
+    let type_params_act = get_type_params_act(
+        type_alias_decl.type_params.as_deref(),
+        Some(type_args),
+        scope,
+    );
+    get_typeact_from_ts_type(
+        &type_alias_decl.type_ann,
+        &scope
+            .with_resolving(&type_alias_name)
+            .with_type_params(type_params_act),
+    )
+}
+
+pub fn get_interface_typeact(
+    interface_decls: &[TsInterfaceDecl],
+    type_args: &[TypeAct],
+    scope: &ActScope,
+) -> TypeAct {
+    let interface_name = interface_decls[0].id.sym.to_string();
+    if let Some(cycle) = scope.get_cycle(&interface_name) {
+        return TypeAct::Unchecked(format!("recursive interface {}", cycle));
+    }
+    // Merged declarations share their type parameters
+    let type_params_act = get_type_params_act(
+        interface_decls[0].type_params.as_deref(),
+        Some(type_args),
+        scope,
+    );
+    let scope = &scope
+        .with_resolving(&interface_name)
+        .with_type_params(type_params_act);
+    let members: Vec<TsTypeElement> = interface_decls
+        .iter()
+        .flat_map(|interface_decl| interface_decl.body.body.clone())
+        .collect();
+    if members.iter().any(is_signature_member) {
+        return get_type_members_typeact(&members, scope);
+    }
+    let mut properties: Vec<PropertyAct> = vec![];
+    // Declarations with the same name are merged
+    for interface_decl in interface_decls {
+        for heritage in &interface_decl.extends {
+            if let Expr::Ident(heritage_ident) = &*heritage.expr {
+                let heritage_name = TsEntityName::Ident(heritage_ident.clone());
+                if let TypeAct::Object(heritage_properties) =
+                    get_type_ref_typeact(&heritage_name, &heritage.type_args, scope)
+                {
+                    properties.extend(heritage_properties)
+                }
+            }
+        }
+        properties.extend(get_properties_act(&interface_decl.body.body, scope));
+    }
+    // Redeclared properties override inherited ones
+    let mut merged_properties: Vec<PropertyAct> = vec![];
+    for property in properties.into_iter().rev() {
+        if !merged_properties
+            .iter()
+            .any(|merged_property| merged_property.name == property.name)
+        {
+            merged_properties.insert(0, property);
+        }
+    }
+    TypeAct::Object(merged_properties)
+}
+
+// Evaluate the constant expression of an enum member initializer
+pub fn get_enum_member_value(
+    init: &Expr,
+    enum_name: &str,
+    members: &[(String, LiteralAct)],
+) -> Option<LiteralAct> {
+    let get_member_value = |member_name: &str| {
+        members
+            .iter()
+            .find(|(name, _)| name == member_name)
+            .map(|(_, value)| value.clone())
+    };
+    match init {
+        Expr::Lit(Lit::Num(number_lit)) => Some(LiteralAct::Number(number_lit.value)),
+        Expr::Lit(Lit::Str(str_lit)) => Some(LiteralAct::String(str_lit.value.to_string())),
+        Expr::Tpl(tpl) if tpl.exprs.is_empty() => Some(LiteralAct::String(
+            tpl.quasis[0].cooked.as_ref()?.to_string(),
+        )),
+        Expr::Paren(paren_expr) => get_enum_member_value(&paren_expr.expr, enum_name, members),
+        // Earlier members can be referred to as `A` or `Enum.A`
+        Expr::Ident(ident) => get_member_value(&ident.sym),
+        Expr::Member(member_expr) => match (&*member_expr.obj, &member_expr.prop) {
+            (Expr::Ident(obj), MemberProp::Ident(prop)) if obj.sym == *enum_name => {
+                get_member_value(&prop.sym)
+            }
+            _ => None,
+        },
+        Expr::Unary(unary_expr) => {
+            let arg = match get_enum_member_value(&unary_expr.arg, enum_name, members)? {
+                LiteralAct::Number(arg) => arg,
+                _ => return None,
+            };
+            match unary_expr.op {
+                UnaryOp::Minus => Some(LiteralAct::Number(-arg)),
+                UnaryOp::Plus => Some(LiteralAct::Number(arg)),
+                UnaryOp::Tilde => Some(LiteralAct::Number(!(arg as i64 as i32) as f64)),
+                _ => None,
+            }
+        }
+        Expr::Bin(bin_expr) => {
+            let left = get_enum_member_value(&bin_expr.left, enum_name, members)?;
+            let right = get_enum_member_value(&bin_expr.right, enum_name, members)?;
+            let (left, right) = match (left, right) {
+                (LiteralAct::Number(left), LiteralAct::Number(right)) => (left, right),
+                (LiteralAct::String(left), LiteralAct::String(right))
+                    if bin_expr.op == BinaryOp::Add =>
+                {
+                    return Some(LiteralAct::String(format!("{}{}", left, right)));
+                }
+                _ => return None,
+            };
+            // Bitwise operators work on 32 bits integers
+            let (left_i32, right_i32) = (left as i64 as i32, right as i64 as i32);
+            let value = match bin_expr.op {
+                BinaryOp::Add => left + right,
+                BinaryOp::Sub => left - right,
+                BinaryOp::Mul => left * right,
+                BinaryOp::Div => left / right,
+                BinaryOp::Mod => left % right,
+                BinaryOp::Exp => left.powf(right),
+                BinaryOp::BitOr => (left_i32 | right_i32) as f64,
+                BinaryOp::BitAnd => (left_i32 & right_i32) as f64,
+                BinaryOp::BitXor => (left_i32 ^ right_i32) as f64,
+                BinaryOp::LShift => left_i32.wrapping_shl(right_i32 as u32) as f64,
+                BinaryOp::RShift => left_i32.wrapping_shr(right_i32 as u32) as f64,
+                BinaryOp::ZeroFillRShift => (left_i32 as u32).wrapping_shr(right_i32 as u32) as f64,
+                _ => return None,
+            };
+            Some(LiteralAct::Number(value))
+        }
+        _ => None,
+    }
+}
+
+pub fn get_enum_members_act(enum_decl: &TsEnumDecl) -> Result<Vec<(String, LiteralAct)>, String> {
+    let enum_name = enum_decl.id.sym.to_string();
+    let mut members: Vec<(String, LiteralAct)> = vec![];
+    for member in &enum_decl.members {
+        let member_name = member.id.as_ref().to_string();
+        let member_value = match &member.init {
+            Some(init) => get_enum_member_value(init, &enum_name, &members),
+            // Members without initializer follow the previous numeric member
+            None => match members.last() {
+                None => Some(LiteralAct::Number(0.0)),
+                Some((_, LiteralAct::Number(previous_value))) => {
+                    Some(LiteralAct::Number(previous_value + 1.0))
+                }
+                Some(_) => None,
+            },
+        };
+        match member_value {
+            Some(member_value) => members.push((member_name, member_value)),
+            None => {
+                return Err(format!(
+                    "enum member {}.{} has a computed value",
+                    enum_name, member_name
+                ))
+            }
+        }
+    }
+    Ok(members)
+}
+
+// Enums are checked against their values, which also covers erased `const enum`
+pub fn get_enum_typeact(enum_decl: &TsEnumDecl) -> TypeAct {
+    let members = match get_enum_members_act(enum_decl) {
+        Ok(members) => members,
+        Err(reason) => return TypeAct::Unchecked(reason),
+    };
+    let mut values_act: Vec<TypeAct> = vec![];
+    for (_, member_value) in members {
+        let value_act = TypeAct::Literal(member_value);
+        if !values_act.contains(&value_act) {
+            values_act.push(value_act);
+        }
+    }
+    if values_act.is_empty() {
+        return TypeAct::Unchecked(format!("enum {} has no members", enum_decl.id.sym));
+    }
+    TypeAct::Union(values_act)
+}
+
+pub fn get_enum_member_typeact(enum_decl: &TsEnumDecl, member_name: &str) -> TypeAct {
+    let members = match get_enum_members_act(enum_decl) {
+        Ok(members) => members,
+        Err(reason) => return TypeAct::Unchecked(reason),
+    };
+    match members.into_iter().find(|(name, _)| name == member_name) {
+        Some((_, member_value)) => TypeAct::Literal(member_value),
+        None => TypeAct::Unknown,
+    }
+}
+
+// Keys of a `Pick`/`Omit`/`Record` key argument, None if they aren't all literals
+pub fn get_literal_keys(keys_type: &TypeAct) -> Option<Vec<String>> {
+    match keys_type {
+        TypeAct::Literal(LiteralAct::String(key)) => Some(vec![key.to_string()]),
+        TypeAct::Literal(LiteralAct::Number(key)) => Some(vec![key.to_string()]),
+        TypeAct::Union(keys_types) => {
+            let mut keys: Vec<String> = vec![];
+            for keys_type in keys_types {
+                keys.extend(get_literal_keys(keys_type)?);
+            }
+            Some(keys)
+        }
+        _ => None,
+    }
+}
+
+pub fn is_index_key_type(key_type: &TypeAct) -> bool {
+    match key_type {
+        TypeAct::String | TypeAct::Number | TypeAct::Symbol => true,
+        TypeAct::Union(keys_types) => keys_types.iter().any(is_index_key_type),
+        _ => false,
+    }
+}
+
+// Properties of an object type, with each of them passed through `map_property`
+pub fn map_object_typeact(
+    object_type: &TypeAct,
+    map_property: impl Fn(PropertyAct) -> Option<PropertyAct>,
+) -> TypeAct {
+    match object_type {
+        TypeAct::Object(properties) => TypeAct::Object(
+            properties
+                .iter()
+                .cloned()
+                .filter_map(map_property)
+                .collect(),
+        ),
+        TypeAct::Unchecked(reason) => TypeAct::Unchecked(reason.to_string()),
+        _ => TypeAct::Unknown,
+    }
+}
+
+// Expand the utility types into the structural type they denote
+pub fn get_utility_typeact(utility_name: &str, type_args: &[TypeAct]) -> Option<TypeAct> {
+    let utility_type = match (utility_name, type_args) {
+        ("Record", [keys_type, value_type]) => {
+            if is_index_key_type(keys_type) {
+                TypeAct::Record(Box::new(value_type.clone()))
+            } else {
+                match get_literal_keys(keys_type) {
+                    Some(keys) => TypeAct::Object(
+                        keys.into_iter()
+                            .map(|key| PropertyAct {
+                                name: key,
+                                act_type: value_type.clone(),
+                                optional: false,
+                            })
+                            .collect(),
+                    ),
+                    None => TypeAct::Unknown,
+                }
+            }
+        }
+        ("Partial", [object_type]) => map_object_typeact(object_type, |property| {
+            Some(PropertyAct {
+                optional: true,
+                ..property
+            })
+        }),
+        ("Required", [object_type]) => map_object_typeact(object_type, |property| {
+            Some(PropertyAct {
+                optional: false,
+                ..property
+            })
+        }),
+        ("Pick", [object_type, keys_type]) => match get_literal_keys(keys_type) {
+            Some(keys) => map_object_typeact(object_type, |property| {
+                keys.contains(&property.name).then_some(property)
+            }),
+            None => TypeAct::Unknown,
+        },
+        ("Omit", [object_type, keys_type]) => match get_literal_keys(keys_type) {
+            Some(keys) => map_object_typeact(object_type, |property| {
+                (!keys.contains(&property.name)).then_some(property)
+            }),
+            None => TypeAct::Unknown,
+        },
+        ("Readonly", [object_type]) => object_type.clone(),
+        ("NonNullable", [TypeAct::Union(types)]) => TypeAct::Union(
+            types
+                .iter()
+                .filter(|union_member| !matches!(union_member, TypeAct::Null | TypeAct::Undefined))
+                .cloned()
+                .collect(),
+        ),
+        ("NonNullable", [nullable_type]) => nullable_type.clone(),
+        _ => return None,
+    };
+    Some(utility_type)
+}
+
+pub fn get_type_ref_typeact(
+    type_name: &TsEntityName,
+    type_params: &Option<Box<TsTypeParamInstantiation>>,
+    scope: &ActScope,
+) -> TypeAct {
+    if let TsEntityName::Ident(ident) = type_name {
+        if let Some(type_param_act) = scope.type_params.get(&*ident.sym) {
+            return type_param_act.clone();
+        }
+    }
+    let type_args: Vec<TypeAct> = match type_params {
+        Some(type_params) => type_params
+            .params
+            .iter()
+            .map(|type_param| get_typeact_from_ts_type(type_param, scope))
+            .collect(),
+        None => vec![],
+    };
+    let (type_ref_type_name, type_decl) = match type_name {
+        TsEntityName::Ident(ident) => (ident.sym.to_string(), scope.find_type_decl(&ident.sym)),
+        TsEntityName::TsQualifiedName(qualified_name) => match &qualified_name.left {
+            TsEntityName::Ident(namespace) => {
+                // `Enum.Member` is the type of a single enum member
+                if let Some((TypeDeclAct::Enum(enum_decl), _)) =
+                    scope.find_type_decl(&namespace.sym)
+                {
+                    return get_enum_member_typeact(enum_decl, &qualified_name.right.sym);
+                }
+                (
+                    format!("{}.{}", namespace.sym, qualified_name.right.sym),
+                    scope.find_namespace_type_decl(&namespace.sym, &qualified_name.right.sym),
+                )
+            }
+            TsEntityName::TsQualifiedName(_) => return TypeAct::Unknown,
+        },
+    };
+    match type_decl {
+        Some((TypeDeclAct::Alias(type_alias_decl), decl_scope)) => {
+            return get_type_alias_typeact(type_alias_decl, &type_args, &decl_scope);
+        }
+        Some((TypeDeclAct::Interface(interface_decls), decl_scope)) => {
+            return get_interface_typeact(interface_decls, &type_args, &decl_scope);
+        }
+        Some((TypeDeclAct::Enum(enum_decl), _)) => return get_enum_typeact(enum_decl),
+        Some((TypeDeclAct::Class, _)) => {
+            // The class is checked with `instanceof` so it must be a value where the check runs
+            let binding_name = type_ref_type_name.split('.').next().unwrap_or_default();
+            if !scope.is_origin_file() {
+                return TypeAct::Unchecked(format!(
+                    "class {} is not in scope of {}",
+                    type_ref_type_name,
+                    scope.origin_file_path.display()
+                ));
+            } else if scope.is_type_only_import(binding_name) {
+                return TypeAct::Unchecked(format!(
+                    "class {} is imported with `import type`",
+                    type_ref_type_name
+                ));
+            }
+            return TypeAct::Class(type_ref_type_name);
+        }
+        _ => {}
+    }
+    if let Some(utility_type) = get_utility_typeact(&type_ref_type_name, &type_args) {
+        return utility_type;
+    }
+    match (type_ref_type_name.as_str(), type_args.as_slice()) {
+        ("Array" | "ReadonlyArray", [elem_type]) => TypeAct::Array(Box::new(elem_type.clone())),
+        ("Map" | "ReadonlyMap", [key_type, value_type]) => {
+            TypeAct::Map(Box::new(key_type.clone()), Box::new(value_type.clone()))
+        }
+        ("Set" | "ReadonlySet", [elem_type]) => TypeAct::Set(Box::new(elem_type.clone())),
+        ("Promise", [result_type]) => TypeAct::Promise(Box::new(result_type.clone())),
+        _ => match get_acttype_from_string(&type_ref_type_name) {
+            TypeAct::Unknown => TypeAct::Reference(type_ref_type_name, type_args),
+            type_act => type_act,
+        },
+    }
+}
+
+pub fn is_primitive_typeact(act_type: &TypeAct) -> bool {
+    match act_type {
+        TypeAct::Number
+        | TypeAct::String
+        | TypeAct::BigInt
+        | TypeAct::Boolean
+        | TypeAct::Symbol
+        | TypeAct::Literal(_)
+        | TypeAct::TemplateLiteral(_) => true,
+        TypeAct::Union(types) => types.iter().all(is_primitive_typeact),
+        _ => false,
+    }
+}
+
+pub fn get_intersection_typeact(types: Vec<TypeAct>) -> TypeAct {
+    // Brands like `string & { __brand: "Email" }` only exist at compile time
+    let types: Vec<TypeAct> = if types.iter().any(is_primitive_typeact) {
+        types
+            .into_iter()
+            .filter(|intersection_member| {
+                !matches!(intersection_member, TypeAct::Object(_) | TypeAct::Record(_))
+            })
+            .collect()
+    } else {
+        types
+    };
+    // Object shapes are merged so a single object check covers them
+    if types
+        .iter()
+        .all(|intersection_member| matches!(intersection_member, TypeAct::Object(_)))
+    {
+        let mut properties: Vec<PropertyAct> = vec![];
+        for intersection_member in types {
+            if let TypeAct::Object(member_properties) = intersection_member {
+                properties.extend(member_properties);
+            }
+        }
+        return TypeAct::Object(properties);
+    }
+    match <[TypeAct; 1]>::try_from(types) {
+        Ok([intersection_member]) => intersection_member,
+        Err(types) => TypeAct::Intersection(types),
+    }
+}
+
+pub fn get_typeact_from_ts_type(ts_type: &TsType, scope: &ActScope) -> TypeAct {
+    match ts_type {
+        TsType::TsKeywordType(keyword_type) => get_typeact_from_typeid(keyword_type.kind),
+        TsType::TsTypeRef(type_ref) => {
+            get_type_ref_typeact(&type_ref.type_name, &type_ref.type_params, scope)
+        }
+        TsType::TsUnionOrIntersectionType(TsUnionOrIntersectionType::TsUnionType(union_type)) => {
+            TypeAct::Union(
+                union_type
+                    .types
+                    .iter()
+                    .map(|union_member| get_typeact_from_ts_type(union_member, scope))
+                    .collect(),
+            )
+        }
+        TsType::TsUnionOrIntersectionType(TsUnionOrIntersectionType::TsIntersectionType(
+            intersection_type,
+        )) => get_intersection_typeact(
+            intersection_type
+                .types
+                .iter()
+                .map(|intersection_member| get_typeact_from_ts_type(intersection_member, scope))
+                .collect(),
+        ),
+        TsType::TsArrayType(array_type) => TypeAct::Array(Box::new(get_typeact_from_ts_type(
+            &array_type.elem_type,
+            scope,
+        ))),
+        TsType::TsTupleType(tuple_type) => TypeAct::Tuple(
+            tuple_type
+                .elem_types
+                .iter()
+                .map(|tuple_element| get_tuple_element_act(tuple_element, scope))
+                .collect(),
+        ),
+        TsType::TsTypeLit(type_lit) => get_type_members_typeact(&type_lit.members, scope),
+        TsType::TsFnOrConstructorType(TsFnOrConstructorType::TsFnType(fn_type)) => {
+            get_signature_typeact(
+                fn_type.type_params.as_deref(),
+                &fn_type.params,
+                Some(&fn_type.type_ann),
+                scope,
+            )
+        }
+        TsType::TsFnOrConstructorType(TsFnOrConstructorType::TsConstructorType(_)) => {
+            TypeAct::Function
+        }
+        TsType::TsLitType(TsLitType {
+            lit: TsLit::Tpl(tpl_lit_type),
+            ..
+        }) => get_template_typeact(tpl_lit_type, scope),
+        TsType::TsLitType(lit_type) => match get_literal_act(&lit_type.lit) {
+            Some(literal) => TypeAct::Literal(literal),
+            None => TypeAct::Unknown,
+        },
+        TsType::TsParenthesizedType(parenthesized_type) => {
+            get_typeact_from_ts_type(&parenthesized_type.type_ann, scope)
+        }
+        TsType::TsTypeOperator(type_operator) if type_operator.op == TsTypeOperatorOp::ReadOnly => {
+            get_typeact_from_ts_type(&type_operator.type_ann, scope)
+        }
+        _ => TypeAct::Unknown,
+    }
+}
+
+pub fn get_param_type_act(param_pat: &Pat, scope: &ActScope) -> TypeAct {
+    match get_param_type_ann(param_pat) {
+        Ok(param_type_ann) => get_typeact_from_ts_type(&param_type_ann, scope),
+        Err(_) => TypeAct::Unknown,
+    }
+}
+
+fn is_param_optional(param_pat: &Pat) -> bool {
+    match param_pat {
+        Pat::Ident(param_ident) => param_ident.optional,
+        Pat::Object(object_pat) => object_pat.optional,
+        Pat::Array(array_pat) => array_pat.optional,
+        _ => false,
+    }
+}
+
+// Literal values that can't refer to any binding
+fn is_constant_expr(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(_) => true,
+        Expr::Tpl(tpl) => tpl.exprs.is_empty(),
+        Expr::Unary(unary_expr) => is_constant_expr(&unary_expr.arg),
+        Expr::Array(array_lit) => array_lit.elems.iter().all(|elem| match elem {
+            Some(elem) => elem.spread.is_none() && is_constant_expr(&elem.expr),
+            None => true,
+        }),
+        Expr::Object(object_lit) => object_lit.props.iter().all(|prop| match prop {
+            PropOrSpread::Prop(prop) => match &**prop {
+                Prop::KeyValue(key_value_prop) => {
+                    !key_value_prop.key.is_computed() && is_constant_expr(&key_value_prop.value)
+                }
+                _ => false,
+            },
+            PropOrSpread::Spread(_) => false,
+        }),
+        _ => false,
+    }
+}
+
+// Source of a destructuring pattern, without its annotation and default value
+fn get_destructure_act(param_pat: &Pat, scope: &ActScope) -> Option<DestructureAct> {
+    let (pattern_span, type_ann) = match param_pat {
+        Pat::Object(object_pat) => (object_pat.span, &object_pat.type_ann),
+        Pat::Array(array_pat) => (array_pat.span, &array_pat.type_ann),
+        Pat::Assign(assign_pat) => return get_destructure_act(&assign_pat.left, scope),
+        _ => return None,
+    };
+    let pattern_end = match type_ann {
+        Some(type_ann) => type_ann.span.lo,
+        None => pattern_span.hi,
+    };
+    let pattern = scope
+        .origin_source
+        .get((pattern_span.lo.0 - 1) as usize..(pattern_end.0 - 1) as usize)?
+        .trim_end();
+    Some(DestructureAct {
+        pattern: pattern.to_string(),
+        byte_pos: pattern_span.lo.0 - 1,
+    })
+}
+
+fn get_default_type_act(param_pat: &Pat) -> Option<TypeAct> {
+    let default_value = match param_pat {
+        Pat::Assign(assign_pat) => &assign_pat.right,
+        _ => return None,
+    };
+    match &**default_value {
+        Expr::Lit(Lit::Str(_)) | Expr::Tpl(_) => Some(TypeAct::String),
+        Expr::Lit(Lit::Num(_)) => Some(TypeAct::Number),
+        Expr::Lit(Lit::Bool(_)) => Some(TypeAct::Boolean),
+        Expr::Lit(Lit::BigInt(_)) => Some(TypeAct::BigInt),
+        _ => None,
+    }
+}
+
+// Destructuring patterns are named by their source
+fn get_param_name(param_pat: Pat, scope: &ActScope) -> String {
     if param_pat.is_ident() {
         param_pat.ident().unwrap().sym.to_string()
+    } else if param_pat.is_assign() {
+        get_param_name(*param_pat.assign().unwrap().left, scope)
+    } else if param_pat.is_rest() {
+        get_param_name(*param_pat.rest().unwrap().arg, scope)
+    } else if let Some(destructure) = get_destructure_act(&param_pat, scope) {
+        destructure.pattern
     } else {
         "unknown".to_string()
     }
 }
-pub fn get_function_params(params: Vec<Pat>) -> Vec<ParamAct> {
+// Names bound by a parameter pattern
+fn get_pat_bound_names(param_pat: &Pat) -> Vec<String> {
+    match param_pat {
+        Pat::Ident(binding_ident) => vec![binding_ident.id.sym.to_string()],
+        Pat::Array(array_pat) => array_pat
+            .elems
+            .iter()
+            .flatten()
+            .flat_map(get_pat_bound_names)
+            .collect(),
+        Pat::Object(object_pat) => object_pat
+            .props
+            .iter()
+            .flat_map(|prop| match prop {
+                ObjectPatProp::KeyValue(key_value_prop) => {
+                    get_pat_bound_names(&key_value_prop.value)
+                }
+                ObjectPatProp::Assign(assign_prop) => vec![assign_prop.key.id.sym.to_string()],
+                ObjectPatProp::Rest(rest_pat) => get_pat_bound_names(&rest_pat.arg),
+            })
+            .collect(),
+        Pat::Rest(rest_pat) => get_pat_bound_names(&rest_pat.arg),
+        Pat::Assign(assign_pat) => get_pat_bound_names(&assign_pat.left),
+        Pat::Invalid(_) | Pat::Expr(_) => vec![],
+    }
+}
+
+#[derive(Default)]
+struct FunctionFinder {
+    found: bool,
+}
+
+impl Visit for FunctionFinder {
+    fn visit_function(&mut self, _: &Function) {
+        self.found = true;
+    }
+
+    fn visit_arrow_expr(&mut self, _: &ArrowExpr) {
+        self.found = true;
+    }
+}
+
+// Functions in the default values of a pattern are checked where they are declared
+fn has_function_default(param_pat: &Pat) -> bool {
+    let mut function_finder = FunctionFinder::default();
+    param_pat.visit_with(&mut function_finder);
+    function_finder.found
+}
+
+// Function declarations of the body are set before it runs, a `var` destructuring would overwrite them
+fn get_hoisted_function_names(body_stmts: &[Stmt]) -> Vec<String> {
+    body_stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Decl(Decl::Fn(fn_decl)) => Some(fn_decl.ident.sym.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn get_function_params(
+    params: Vec<Pat>,
+    body_stmts: &[Stmt],
+    scope: &ActScope,
+) -> Vec<ParamAct> {
     let mut params_act: Vec<ParamAct> = vec![];
-    for param in params {
-        let param_type_act = get_param_type_act(&param);
-        let param_name = get_param_name(param);
+    let hoisted_function_names = get_hoisted_function_names(body_stmts);
+    let last_default_index = params.iter().rposition(|param| match param {
+        Pat::Assign(assign_pat) => !is_constant_expr(&assign_pat.right),
+        _ => false,
+    });
+    for (param_index, param) in params.into_iter().enumerate() {
+        let mut param_type_act = get_param_type_act(&param, scope);
+        let param_optional = is_param_optional(&param);
+        let param_rest = param.is_rest();
+        if let Pat::Rest(rest_pat) = &param {
+            if !rest_pat.arg.is_ident() {
+                param_type_act = TypeAct::Unchecked("destructured rest parameter".to_string());
+            }
+        }
+        let param_default_type = get_default_type_act(&param);
+        let mut param_destructure = get_destructure_act(&param, scope);
+        let param_bound_names = get_pat_bound_names(&param);
+        let param_has_function_default = has_function_default(&param);
+        let mut param_name = get_param_name(param, scope);
+        // Non constant defaults of the next parameters could use the names bound by the pattern
+        if param_destructure.is_some()
+            && last_default_index.is_some_and(|default_index| default_index > param_index)
+        {
+            param_destructure = None;
+            param_type_act = TypeAct::Unchecked(
+                "destructured parameter is followed by a default value".to_string(),
+            );
+        } else if param_destructure.is_some()
+            && param_bound_names
+                .iter()
+                .any(|bound_name| hoisted_function_names.contains(bound_name))
+        {
+            param_destructure = None;
+            param_type_act = TypeAct::Unchecked(
+                "destructured parameter is shadowed by a function declaration".to_string(),
+            );
+        } else if param_destructure.is_some() && param_has_function_default {
+            param_destructure = None;
+            param_type_act = TypeAct::Unchecked(
+                "destructured parameter declares a function in a default value".to_string(),
+            );
+        } else if param_destructure.is_some() {
+            param_name = format!("__act_param_{}", param_index);
+        }
         params_act.push(ParamAct {
             name: param_name,
             act_type: param_type_act,
+            optional: param_optional,
+            rest: param_rest,
+            default_type: param_default_type,
+            destructure: param_destructure,
+            property: false,
         })
     }
     params_act
 }
 
-pub fn get_function_act(function_name: String, function: Box<Function>) -> FunctionAct {
+pub fn get_function_act(
+    function_name: String,
+    function: &Function,
+    scope: &ActScope,
+) -> FunctionAct {
     if function.body.is_none() {
         panic!("Function body is empty get_function_act should not be called");
     }
-    let function_body = function.body.unwrap();
-    let function_body_start = function_body.span.lo.0;
+    let function_body = function.body.as_ref().unwrap();
+    let function_body_start = get_body_start(function_body);
+    let scope = &scope.with_type_params(get_type_params_act(
+        function.type_params.as_deref(),
+        None,
+        scope,
+    ));
     let function_act: FunctionAct = FunctionAct {
         name: function_name,
-        params: get_function_params(get_pat_from_param(function.params)),
+        params: get_function_params(
+            get_pat_from_param(function.params.clone()),
+            &function_body.stmts,
+            scope,
+        ),
         body_start: function_body_start,
     };
     function_act
 }
 
-pub fn get_function_patches(function_act: FunctionAct, file_name: &Path) -> Vec<PatchAct> {
+pub fn get_function_patches(
+    function_act: FunctionAct,
+    file_name: &Path,
+    args: &ActArgs,
+) -> Vec<PatchAct> {
     let mut patches: Vec<PatchAct> = vec![];
     patches.extend(get_function_params_patches(
         function_act.params,
         function_act.body_start,
+        function_act.body_start,
         function_act.name,
         file_name.to_str().unwrap().to_string(),
+        args,
     ));
     patches
 }
 
-pub fn process_function_decl(fn_decl: FnDecl, file_path: &Path) -> Vec<PatchAct> {
-    let function_name = fn_decl.ident.sym.to_string();
-    if fn_decl.function.body.is_some() {
-        let function_act = get_function_act(function_name, fn_decl.function);
-        let function_patches: Vec<PatchAct> = get_function_patches(function_act, file_path);
-        function_patches
-    } else {
-        vec![]
-    }
-}
-pub fn process_var_decl(var_decl: Box<VarDecl>, file_path: &Path) -> Vec<PatchAct> {
-    let var_decl_decls = var_decl.decls;
-    let mut patches: Vec<PatchAct> = vec![];
-    for var_decl_decl in var_decl_decls {
-        let var_decl_decl_name = var_decl_decl.name;
-        let var_decl_decl_init = var_decl_decl.init;
-        if var_decl_decl_init.is_some() {
-            let var_decl_decl_init_wraped = var_decl_decl_init.unwrap();
-            if var_decl_decl_init_wraped.is_fn_expr() {
-                let fn_expr = var_decl_decl_init_wraped.fn_expr().unwrap();
-                if var_decl_decl_name.is_ident() {
-                    let function_name = var_decl_decl_name.ident().unwrap().sym.to_string();
-                    let function_act = get_function_act(function_name, fn_expr.function);
-                    patches.extend(get_function_patches(function_act, file_path));
-                } else {
-                    let function_name = "unknonVarName".to_string();
-                    let function_act = get_function_act(function_name, fn_expr.function);
-                    patches.extend(get_function_patches(function_act, file_path));
-                }
-            } else if var_decl_decl_init_wraped.is_arrow() {
-                let arrow_expr = var_decl_decl_init_wraped.arrow().unwrap();
-                let function_body = arrow_expr.body;
-                if function_body.is_block_stmt() {
-                    if var_decl_decl_name.is_ident() {
-                        let function_name = var_decl_decl_name.ident().unwrap().sym.to_string();
-                        let function_body_block_stmt = function_body.block_stmt().unwrap();
-                        let function_body_start = function_body_block_stmt.span.lo.0;
-                        let function_act: FunctionAct = FunctionAct {
-                            name: function_name,
-                            params: get_function_params(arrow_expr.params),
-                            body_start: function_body_start,
-                        };
-                        let function_patches: Vec<PatchAct> =
-                            get_function_patches(function_act, file_path);
-                        patches.extend(function_patches);
-                    } else {
-                        let function_name = "unknonVarName".to_string();
-                        let function_body_block_stmt = function_body.block_stmt().unwrap();
-                        let function_body_start = function_body_block_stmt.span.lo.0;
-                        let function_act: FunctionAct = FunctionAct {
-                            name: function_name,
-                            params: get_function_params(arrow_expr.params),
-                            body_start: function_body_start,
-                        };
-                        let function_patches: Vec<PatchAct> =
-                            get_function_patches(function_act, file_path);
-                        patches.extend(function_patches);
-                    }
-                }
+pub fn get_arrow_patches(
+    function_name: String,
+    arrow_expr: &ArrowExpr,
+    file_path: &Path,
+    scope: &ActScope,
+) -> Vec<PatchAct> {
+    let scope = &scope.with_type_params(get_type_params_act(
+        arrow_expr.type_params.as_deref(),
+        None,
+        scope,
+    ));
+    match &*arrow_expr.body {
+        BlockStmtOrExpr::BlockStmt(function_body_block_stmt) => {
+            let function_act: FunctionAct = FunctionAct {
+                name: function_name,
+                params: get_function_params(
+                    arrow_expr.params.clone(),
+                    &function_body_block_stmt.stmts,
+                    scope,
+                ),
+                body_start: get_body_start(function_body_block_stmt),
+            };
+            get_function_patches(function_act, file_path, scope.args)
+        }
+        // `x => expr` becomes `x => {<checks> return expr; }`, the span of a parenthesized
+        // object literal includes its parentheses so it is returned as is
+        BlockStmtOrExpr::Expr(function_body_expr) => {
+            let function_body_span = function_body_expr.span();
+            let function_body_start = function_body_span.lo.0 - 1;
+            let function_act: FunctionAct = FunctionAct {
+                name: function_name,
+                params: get_function_params(arrow_expr.params.clone(), &[], scope),
+                body_start: function_body_start,
+            };
+            let function_patches = get_function_patches(function_act, file_path, scope.args);
+            if function_patches.is_empty() {
+                return function_patches;
             }
+            let mut patches: Vec<PatchAct> = vec![PatchAct {
+                byte_pos: function_body_start,
+                patch: "{".as_bytes().to_vec(),
+                replaced_len: 0,
+            }];
+            patches.extend(function_patches);
+            patches.push(PatchAct {
+                byte_pos: function_body_start,
+                patch: "return ".as_bytes().to_vec(),
+                replaced_len: 0,
+            });
+            patches.push(PatchAct {
+                byte_pos: function_body_span.hi.0 - 1,
+                patch: "; }".as_bytes().to_vec(),
+                replaced_len: 0,
+            });
+            patches
         }
     }
-    patches
-}
-pub fn process_function_expr(fn_expr: FnExpr, file_path: &Path) -> Vec<PatchAct> {
-    let function_name = fn_expr.ident.unwrap().sym.to_string();
-    if fn_expr.function.body.is_some() {
-        let function_act = get_function_act(function_name, fn_expr.function);
-        let function_patches: Vec<PatchAct> = get_function_patches(function_act, file_path);
-        function_patches
-    } else {
-        vec![]
-    }
 }
 
-pub fn process_function_arrow(arrow_expr: ArrowExpr, file_path: &Path) -> Vec<PatchAct> {
-    let function_body = arrow_expr.body;
-    if function_body.is_block_stmt() {
-        let function_body_block_stmt = function_body.block_stmt().unwrap();
-        let function_body_start = function_body_block_stmt.span.lo.0;
-        let function_act: FunctionAct = FunctionAct {
-            name: "AnonymousFunction".to_string(),
-            params: get_function_params(arrow_expr.params),
-            body_start: function_body_start,
-        };
-        let function_patches: Vec<PatchAct> = get_function_patches(function_act, file_path);
-        return function_patches;
-    }
-    vec![]
-}
-
-pub fn get_class_act(class_decl: ClassDecl) -> ClassAct {
-    let class_name = class_decl.ident.sym.to_string();
-    let class = class_decl.class;
-    let class_props = class.body;
+pub fn get_class_act(class_name: String, class: &Class, scope: &ActScope) -> ClassAct {
+    let is_derived_class = class.super_class.is_some();
+    // Type parameters of the class apply to every method
+    let scope = &scope.with_type_params(get_type_params_act(
+        class.type_params.as_deref(),
+        None,
+        scope,
+    ));
+    let class_props = &class.body;
     let mut methods_act: Vec<MethodAct> = vec![];
     for class_prop in class_props {
-        if class_prop.is_method() {
-            let method = class_prop.method().unwrap();
-            let method_key = method.key;
-            let mut method_name: String = "unknownName".to_string();
-            if method_key.is_ident() {
-                let method_key_ident = method_key.ident().unwrap();
-                method_name = method_key_ident.sym.to_string();
+        if let Some(method) = class_prop.as_method() {
+            let method_name = get_prop_name(&method.key);
+            if method.function.body.is_some() {
+                let function_act = get_function_act(method_name, &method.function, scope);
+                let method_act: MethodAct = MethodAct {
+                    function: function_act,
+                    super_call_end: None,
+                };
+                methods_act.push(method_act)
             }
+        } else if let Some(method) = class_prop.as_private_method() {
+            let method_name = format!("#{}", method.key.id.sym);
             if method.function.body.is_some() {
-                let function_act = get_function_act(method_name, method.function);
+                let function_act = get_function_act(method_name, &method.function, scope);
                 let method_act: MethodAct = MethodAct {
                     function: function_act,
+                    super_call_end: None,
                 };
                 methods_act.push(method_act)
             }
-        } else if class_prop.is_constructor() {
-            let constructor = class_prop.constructor().unwrap();
+        } else if let Some(constructor) = class_prop.as_constructor() {
             if constructor.params.is_empty() {
                 continue;
             }
-            if constructor.body.is_some() {
-                let constructor_body = constructor.body.unwrap();
-                let constructor_body_start = constructor_body.span.lo.0;
-                let mut params: Vec<Param> = vec![];
-                for param in constructor.params {
-                    if param.is_param() {
-                        params.push(param.param().unwrap())
+            if let Some(constructor_body) = &constructor.body {
+                let mut params: Vec<Pat> = vec![];
+                let mut properties: Vec<bool> = vec![];
+                for param in constructor.params.clone() {
+                    properties.push(param.is_ts_param_prop());
+                    match param {
+                        ParamOrTsParamProp::Param(param) => params.push(param.pat),
+                        ParamOrTsParamProp::TsParamProp(param_prop) => {
+                            params.push(match param_prop.param {
+                                TsParamPropParam::Ident(binding_ident) => Pat::Ident(binding_ident),
+                                TsParamPropParam::Assign(assign_pat) => Pat::Assign(assign_pat),
+                            })
+                        }
                     }
                 }
+                let mut params = get_function_params(params, &constructor_body.stmts, scope);
+                for (param, property) in params.iter_mut().zip(properties) {
+                    param.property = property;
+                }
+                let super_call_end = match is_derived_class && has_instance_initializers(class) {
+                    true => get_super_call_end(constructor_body),
+                    false => None,
+                };
                 let constructor_act: MethodAct = MethodAct {
                     function: FunctionAct {
                         name: "constructor".to_string(),
-                        params: get_function_params(get_pat_from_param(params)),
-                        body_start: constructor_body_start,
+                        params,
+                        body_start: get_body_start(constructor_body),
                     },
+                    super_call_end,
                 };
                 methods_act.push(constructor_act)
             }
@@ -262,6 +1203,62 @@ pub fn get_class_act(class_decl: ClassDecl) -> ClassAct {
     class_act
 }
 
+// Parameter properties and initialized fields are assigned right after `super()`, which
+// then has to be the first statement of a derived constructor
+fn has_instance_initializers(class: &Class) -> bool {
+    class.body.iter().any(|class_member| match class_member {
+        ClassMember::Constructor(constructor) => constructor
+            .params
+            .iter()
+            .any(|param| param.is_ts_param_prop()),
+        ClassMember::ClassProp(class_prop) => !class_prop.is_static && class_prop.value.is_some(),
+        ClassMember::PrivateProp(private_prop) => {
+            !private_prop.is_static && private_prop.value.is_some()
+        }
+        _ => false,
+    })
+}
+
+// Checks are inserted right after the top level `super()` call when it has to come first
+fn get_super_call_end(constructor_body: &BlockStmt) -> Option<u32> {
+    constructor_body.stmts.iter().find_map(|stmt| match stmt {
+        Stmt::Expr(expr_stmt) => match &*expr_stmt.expr {
+            Expr::Call(call_expr) if matches!(call_expr.callee, Callee::Super(_)) => {
+                Some(expr_stmt.span.hi.0 - 1)
+            }
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+// A string literal statement is only a directive while nothing else precedes it,
+// checks are inserted after the last one so `"use strict"` keeps its meaning
+fn get_directives_end(body: &BlockStmt) -> Option<u32> {
+    body.stmts
+        .iter()
+        .map_while(|stmt| match stmt {
+            Stmt::Expr(expr_stmt) if matches!(&*expr_stmt.expr, Expr::Lit(Lit::Str(_))) => {
+                Some(expr_stmt.span.hi.0 - 1)
+            }
+            _ => None,
+        })
+        .last()
+}
+
+pub fn get_body_start(body: &BlockStmt) -> u32 {
+    get_directives_end(body).unwrap_or(body.span.lo.0)
+}
+
+pub fn get_prop_name(prop_name: &PropName) -> String {
+    match prop_name {
+        PropName::Ident(ident) => ident.sym.to_string(),
+        PropName::Str(str) => str.value.to_string(),
+        PropName::Num(num) => num.value.to_string(),
+        _ => "unknownName".to_string(),
+    }
+}
+
 pub fn get_pat_from_param(params: Vec<Param>) -> Vec<Pat> {
     let mut pats: Vec<Pat> = vec![];
     for param in params {
@@ -270,83 +1267,216 @@ pub fn get_pat_from_param(params: Vec<Param>) -> Vec<Pat> {
     pats
 }
 
-pub fn get_class_patches(class_act: ClassAct, file_path: &Path) -> Vec<PatchAct> {
+pub fn get_class_patches(class_act: ClassAct, file_path: &Path, args: &ActArgs) -> Vec<PatchAct> {
     let mut patches: Vec<PatchAct> = vec![];
-    patches.extend(get_methods_patches(class_act, file_path));
+    patches.extend(get_methods_patches(class_act, file_path, args));
     patches
 }
 
-fn get_methods_patches(class_act: ClassAct, file_path: &Path) -> Vec<PatchAct> {
+fn get_methods_patches(class_act: ClassAct, file_path: &Path, args: &ActArgs) -> Vec<PatchAct> {
     let mut patches: Vec<PatchAct> = vec![];
     for method in class_act.methods {
         patches.extend(get_function_params_patches(
             method.function.params,
             method.function.body_start,
+            method.super_call_end.unwrap_or(method.function.body_start),
             method.function.name,
             file_path.to_str().unwrap().to_string(),
+            args,
         ));
     }
     patches
 }
 
-pub fn process_class_decl(class_decl: ClassDecl, file_path: &Path) -> Vec<PatchAct> {
-    let class_act = get_class_act(class_decl);
-    let class_patches: Vec<PatchAct> = get_class_patches(class_act, file_path);
-    class_patches
+// Finds the functions of a module at any depth and generates their checks, a function's
+// own patches are generated before the ones of the functions nested in it
+pub struct FunctionsVisitor<'a, 'b> {
+    file_path: &'b Path,
+    // Scope of the visited node, with the type parameters of the enclosing generics
+    scope: ActScope<'a>,
+    // Name of the variable or property the next visited function is assigned to
+    function_name: Option<String>,
+    pub patches: Vec<PatchAct>,
 }
 
-pub fn process_decl(decl: Decl, file_path: &Path) -> Vec<PatchAct> {
-    if decl.is_fn_decl() {
-        let fn_decl = decl.fn_decl().unwrap();
-        process_function_decl(fn_decl, file_path)
-    } else if decl.is_class() {
-        let class_decl = decl.class().unwrap();
-        return process_class_decl(class_decl, file_path);
-    } else if decl.is_var() {
-        return process_var_decl(decl.var().unwrap(), file_path);
-    } else {
-        return vec![];
+impl<'a, 'b> FunctionsVisitor<'a, 'b> {
+    pub fn new(file_path: &'b Path, scope: ActScope<'a>) -> FunctionsVisitor<'a, 'b> {
+        FunctionsVisitor {
+            file_path,
+            scope,
+            function_name: None,
+            patches: vec![],
+        }
+    }
+
+    fn take_function_name(&mut self, default_name: String) -> String {
+        self.function_name.take().unwrap_or(default_name)
+    }
+
+    fn visit_named_expr(&mut self, function_name: String, expr: &Expr) {
+        if expr.is_fn_expr() || expr.is_arrow() {
+            self.function_name = Some(function_name);
+        }
+        expr.visit_with(self);
+    }
+
+    fn visit_children_with_type_params<N: VisitWith<Self>>(
+        &mut self,
+        node: &N,
+        type_params: Option<&TsTypeParamDecl>,
+    ) {
+        let type_params_act = get_type_params_act(type_params, None, &self.scope);
+        let inner_scope = self.scope.with_type_params(type_params_act);
+        let outer_scope = std::mem::replace(&mut self.scope, inner_scope);
+        node.visit_children_with(self);
+        self.scope = outer_scope;
+    }
+
+    fn check_function(&mut self, function_name: String, function: &Function) {
+        if function.body.is_some() {
+            let function_act = get_function_act(function_name, function, &self.scope);
+            self.patches.extend(get_function_patches(
+                function_act,
+                self.file_path,
+                self.scope.args,
+            ));
+        }
+    }
+
+    fn check_class(&mut self, class_name: String, class: &Class) {
+        let class_act = get_class_act(class_name, class, &self.scope);
+        self.patches.extend(get_class_patches(
+            class_act,
+            self.file_path,
+            self.scope.args,
+        ));
+    }
+}
+
+impl Visit for FunctionsVisitor<'_, '_> {
+    fn visit_function(&mut self, function: &Function) {
+        self.visit_children_with_type_params(function, function.type_params.as_deref());
+    }
+
+    fn visit_class(&mut self, class: &Class) {
+        self.visit_children_with_type_params(class, class.type_params.as_deref());
+    }
+
+    fn visit_fn_decl(&mut self, fn_decl: &FnDecl) {
+        self.check_function(fn_decl.ident.sym.to_string(), &fn_decl.function);
+        fn_decl.visit_children_with(self);
+    }
+
+    fn visit_fn_expr(&mut self, fn_expr: &FnExpr) {
+        let function_name = match &fn_expr.ident {
+            Some(fn_expr_ident) => fn_expr_ident.sym.to_string(),
+            None => "AnonymousFunction".to_string(),
+        };
+        let function_name = self.take_function_name(function_name);
+        self.check_function(function_name, &fn_expr.function);
+        fn_expr.visit_children_with(self);
+    }
+
+    fn visit_arrow_expr(&mut self, arrow_expr: &ArrowExpr) {
+        let function_name = self.take_function_name("AnonymousFunction".to_string());
+        self.patches.extend(get_arrow_patches(
+            function_name,
+            arrow_expr,
+            self.file_path,
+            &self.scope,
+        ));
+        self.visit_children_with_type_params(arrow_expr, arrow_expr.type_params.as_deref());
+    }
+
+    fn visit_class_decl(&mut self, class_decl: &ClassDecl) {
+        self.check_class(class_decl.ident.sym.to_string(), &class_decl.class);
+        class_decl.visit_children_with(self);
+    }
+
+    fn visit_class_expr(&mut self, class_expr: &ClassExpr) {
+        let class_name = match &class_expr.ident {
+            Some(class_expr_ident) => class_expr_ident.sym.to_string(),
+            None => "AnonymousClass".to_string(),
+        };
+        self.check_class(class_name, &class_expr.class);
+        class_expr.visit_children_with(self);
+    }
+
+    fn visit_var_declarator(&mut self, var_declarator: &VarDeclarator) {
+        var_declarator.name.visit_with(self);
+        if let Some(init) = &var_declarator.init {
+            let function_name = match var_declarator.name.as_ident() {
+                Some(var_declarator_ident) => var_declarator_ident.sym.to_string(),
+                None => "unknonVarName".to_string(),
+            };
+            self.visit_named_expr(function_name, init);
+        }
+    }
+
+    fn visit_key_value_prop(&mut self, key_value_prop: &KeyValueProp) {
+        key_value_prop.key.visit_with(self);
+        self.visit_named_expr(get_prop_name(&key_value_prop.key), &key_value_prop.value);
+    }
+
+    fn visit_class_prop(&mut self, class_prop: &ClassProp) {
+        class_prop.decorators.visit_with(self);
+        class_prop.key.visit_with(self);
+        if let Some(value) = &class_prop.value {
+            self.visit_named_expr(get_prop_name(&class_prop.key), value);
+        }
+    }
+
+    fn visit_private_prop(&mut self, private_prop: &PrivateProp) {
+        private_prop.decorators.visit_with(self);
+        if let Some(value) = &private_prop.value {
+            self.visit_named_expr(format!("#{}", private_prop.key.id.sym), value);
+        }
+    }
+
+    fn visit_method_prop(&mut self, method_prop: &MethodProp) {
+        self.check_function(get_prop_name(&method_prop.key), &method_prop.function);
+        method_prop.visit_children_with(self);
+    }
+
+    fn visit_setter_prop(&mut self, setter_prop: &SetterProp) {
+        if let Some(setter_body) = &setter_prop.body {
+            let function_act = FunctionAct {
+                name: get_prop_name(&setter_prop.key),
+                params: get_function_params(
+                    vec![(*setter_prop.param).clone()],
+                    &setter_body.stmts,
+                    &self.scope,
+                ),
+                body_start: get_body_start(setter_body),
+            };
+            self.patches.extend(get_function_patches(
+                function_act,
+                self.file_path,
+                self.scope.args,
+            ));
+        }
+        setter_prop.visit_children_with(self);
     }
 }
 
 pub fn process_module_items(
     module_items: Vec<ModuleItem>,
     file_path: &Path,
+    source: &str,
+    project_types: &ProjectTypesAct,
+    args: &ActArgs,
 ) -> Result<Vec<PatchAct>, String> {
-    let mut patches: Vec<PatchAct> = vec![];
-    for item in module_items {
-        if item.is_stmt() {
-            let stmt = item.stmt().unwrap();
-            if stmt.is_decl() {
-                let decl = stmt.decl().unwrap();
-                patches.extend(process_decl(decl, file_path));
-            } else if stmt.is_expr() {
-                let expr = stmt.expr().unwrap().expr;
-                if expr.is_fn_expr() {
-                    let fn_expr = expr.fn_expr().unwrap();
-                    patches.extend(process_function_expr(fn_expr, file_path));
-                } else if expr.is_arrow() {
-                    let arrow_expr = expr.arrow().unwrap();
-                    patches.extend(process_function_arrow(arrow_expr, file_path));
-                }
-            }
-        } else if item.is_module_decl() {
-            let module_decl = item.module_decl().unwrap();
-            if module_decl.is_export_decl() {
-                let export_decl = module_decl.export_decl().unwrap();
-                let decl = export_decl.decl;
-                patches.extend(process_decl(decl, file_path));
-            }
-        }
-    }
-    Ok(patches)
+    let file_types = get_file_types_act(&module_items);
+    let scope = ActScope::new(&file_types, file_path, source, project_types, args);
+    let mut functions_visitor = FunctionsVisitor::new(file_path, scope);
+    functions_visitor.visit_module_items(&module_items);
+    Ok(functions_visitor.patches)
 }
 
-pub fn process_file(file_path: PathBuf) -> Result<(), String> {
-    println!("analysing file {}", file_path.to_str().unwrap());
+pub fn parse_file(file_path: &Path) -> Result<Module, String> {
     let cm: Lrc<SourceMap> = Default::default();
 
-    let fm = cm.load_file(&file_path).expect("failed to load ts file");
+    let fm = cm.load_file(file_path).expect("failed to load ts file");
     let lexer = Lexer::new(
         Syntax::Typescript(TsConfig {
             decorators: true,
@@ -362,25 +1492,928 @@ pub fn process_file(file_path: PathBuf) -> Result<(), String> {
 
     let mut parser = Parser::new_from(lexer);
 
-    let module_wrap = parser.parse_typescript_module();
+    parser
+        .parse_typescript_module()
+        .map_err(|err| format!("{:?}", err.into_kind()))
+}
+
+pub fn get_project_types_act(files_paths: &[PathBuf]) -> ProjectTypesAct {
+    let mut project_types = ProjectTypesAct::default();
+    for file_path in files_paths {
+        // Parsing errors are reported when the file itself is processed
+        if let Ok(module) = parse_file(file_path) {
+            project_types
+                .files
+                .insert(normalize_path(file_path), get_file_types_act(&module.body));
+        }
+    }
+    project_types
+}
+
+pub fn process_file(file_path: PathBuf, project_types: &ProjectTypesAct) -> Result<(), String> {
+    println!("analysing file {}", file_path.to_str().unwrap());
     let mut patches: Vec<PatchAct> = vec![];
-    if module_wrap.is_ok() {
-        let module = module_wrap.unwrap();
-        let module_item_wrap = process_module_items(module.body, &file_path);
-        if module_item_wrap.is_ok() {
-            patches = module_item_wrap.unwrap();
-        } else if module_item_wrap.is_err() {
-            println!("error processing file {}", file_path.to_str().unwrap());
-            let err = module_item_wrap.err().unwrap();
-            println!("{:?}", err);
-        }
-    } else if module_wrap.is_err() {
-        println!("error parsing file {}", file_path.to_str().unwrap());
-        let err = module_wrap.err().unwrap();
-        println!("{:?}", err.into_kind());
+    let source = fs::read_to_string(&file_path).unwrap_or_default();
+    let args = ActArgs::parse();
+    match parse_file(&file_path) {
+        Ok(module) => {
+            match process_module_items(module.body, &file_path, &source, project_types, &args) {
+                Ok(module_patches) => patches = module_patches,
+                Err(err) => {
+                    println!("error processing file {}", file_path.to_str().unwrap());
+                    println!("{:?}", err);
+                }
+            }
+        }
+        Err(err) => {
+            println!("error parsing file {}", file_path.to_str().unwrap());
+            println!("{}", err);
+        }
     }
 
     apply_patches(patches, file_path).unwrap();
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::act_patch::{gen_param_type_check_patch, gen_type_check, get_patched_buffer};
+    use swc_common::FileName;
+
+    fn default_args() -> ActArgs {
+        ActArgs::parse_from(["actypes"])
+    }
+
+    fn parse_module_items(source: &str) -> Vec<ModuleItem> {
+        let cm: Lrc<SourceMap> = Default::default();
+        let fm = cm.new_source_file(FileName::Anon, source.to_string());
+        let lexer = Lexer::new(
+            Syntax::Typescript(TsConfig::default()),
+            EsVersion::EsNext,
+            StringInput::from(&*fm),
+            None,
+        );
+        let mut parser = Parser::new_from(lexer);
+        parser.parse_typescript_module().unwrap().body
+    }
+
+    // Source of a `src/test.ts` file once its checks are inserted
+    fn get_patched_source(source: &str) -> String {
+        let patches = process_module_items(
+            parse_module_items(source),
+            Path::new("src/test.ts"),
+            source,
+            &ProjectTypesAct::default(),
+            &default_args(),
+        )
+        .unwrap();
+        String::from_utf8(get_patched_buffer(source.as_bytes().to_vec(), patches)).unwrap()
+    }
+
+    // Check inserted for a `typeof` checked parameter, as `get_patched_source` generates it
+    fn get_typeof_check(symbol_name: &str, name: &str, ts_type: &str) -> String {
+        format!(
+            r#"
+    if(!(typeof {name} === '{ts_type}')){{
+    console.warn(`[src/test.ts=>{symbol_name}] {name} isn't of type {ts_type} but of type ${{{name} === null ? "null" : typeof {name}}}`);
+    }}
+    "#
+        )
+    }
+
+    fn get_first_function_params(source: &str) -> Vec<ParamAct> {
+        get_first_function_params_in_project(source, &[])
+    }
+
+    fn get_first_function_params_in_project(
+        source: &str,
+        project_files: &[(&str, &str)],
+    ) -> Vec<ParamAct> {
+        let mut project_types = ProjectTypesAct::default();
+        for (project_file_path, project_file_source) in project_files {
+            project_types.files.insert(
+                PathBuf::from(project_file_path),
+                get_file_types_act(&parse_module_items(project_file_source)),
+            );
+        }
+        let module_items = parse_module_items(source);
+        let file_types = get_file_types_act(&module_items);
+        let args = default_args();
+        let scope = &ActScope::new(
+            &file_types,
+            Path::new("src/test.ts"),
+            source,
+            &project_types,
+            &args,
+        );
+        let fn_decl = module_items
+            .into_iter()
+            .find_map(|item| item.stmt()?.decl()?.fn_decl())
+            .unwrap();
+        get_function_act("test".to_string(), &fn_decl.function, scope).params
+    }
+
+    fn get_property(name: &str, act_type: TypeAct, optional: bool) -> PropertyAct {
+        PropertyAct {
+            name: name.to_string(),
+            act_type,
+            optional,
+        }
+    }
+
+    #[test]
+    fn ts_type_tree_to_typeact_test() {
+        let params = get_first_function_params(
+            r#"function test(a: string | number, b: number[], c: { id: string; n?: "x" }, d: Foo<bigint>) {}"#,
+        );
+        assert_eq!(
+            params[0].act_type,
+            TypeAct::Union(vec![TypeAct::String, TypeAct::Number])
+        );
+        assert_eq!(
+            params[1].act_type,
+            TypeAct::Array(Box::new(TypeAct::Number))
+        );
+        assert_eq!(
+            params[2].act_type,
+            TypeAct::Object(vec![
+                get_property("id", TypeAct::String, false),
+                get_property(
+                    "n",
+                    TypeAct::Literal(LiteralAct::String("x".to_string())),
+                    true
+                ),
+            ])
+        );
+        assert_eq!(
+            params[3].act_type,
+            TypeAct::Reference("Foo".to_string(), vec![TypeAct::BigInt])
+        );
+    }
+
+    #[test]
+    fn literal_type_test() {
+        let params = get_first_function_params(
+            r#"function test(a: "asc" | "desc", b: -1 | 2n, c: true) {}"#,
+        );
+        assert_eq!(
+            params[0].act_type,
+            TypeAct::Union(vec![
+                TypeAct::Literal(LiteralAct::String("asc".to_string())),
+                TypeAct::Literal(LiteralAct::String("desc".to_string())),
+            ])
+        );
+        assert_eq!(
+            params[1].act_type,
+            TypeAct::Union(vec![
+                TypeAct::Literal(LiteralAct::Number(-1.0)),
+                TypeAct::Literal(LiteralAct::BigInt("2".to_string())),
+            ])
+        );
+        assert_eq!(
+            params[2].act_type,
+            TypeAct::Literal(LiteralAct::Boolean(true))
+        );
+    }
+
+    #[test]
+    fn array_type_test() {
+        let params = get_first_function_params(
+            r#"function test(a: Array<string>, b: ReadonlyArray<number>, c: readonly bigint[]) {}"#,
+        );
+        assert_eq!(
+            params[0].act_type,
+            TypeAct::Array(Box::new(TypeAct::String))
+        );
+        assert_eq!(
+            params[1].act_type,
+            TypeAct::Array(Box::new(TypeAct::Number))
+        );
+        assert_eq!(
+            params[2].act_type,
+            TypeAct::Array(Box::new(TypeAct::BigInt))
+        );
+    }
+
+    #[test]
+    fn tuple_type_test() {
+        let params = get_first_function_params(
+            r#"function test(a: [string, number?, ...boolean[]], b: [x: number, y?: number, ...rest: string[]]) {}"#,
+        );
+        assert_eq!(
+            params[0].act_type,
+            TypeAct::Tuple(vec![
+                TupleElementAct::Required(TypeAct::String),
+                TupleElementAct::Optional(TypeAct::Number),
+                TupleElementAct::Rest(TypeAct::Boolean),
+            ])
+        );
+        assert_eq!(
+            params[1].act_type,
+            TypeAct::Tuple(vec![
+                TupleElementAct::Required(TypeAct::Number),
+                TupleElementAct::Optional(TypeAct::Number),
+                TupleElementAct::Rest(TypeAct::String),
+            ])
+        );
+    }
+
+    #[test]
+    fn type_alias_test() {
+        let params = get_first_function_params(
+            r#"
+            type int = number;
+            export type maybeInt = int | undefined;
+            type loop = loop2;
+            type loop2 = loop;
+            function test(a: int, b: maybeInt, c: loop) {}"#,
+        );
+        assert_eq!(params[0].act_type, TypeAct::Number);
+        assert_eq!(
+            params[1].act_type,
+            TypeAct::Union(vec![TypeAct::Number, TypeAct::Undefined])
+        );
+        assert_eq!(
+            params[2].act_type,
+            TypeAct::Unchecked("circular type alias loop -> loop2 -> loop".to_string())
+        );
+    }
+
+    #[test]
+    fn imported_type_test() {
+        let params = get_first_function_params_in_project(
+            r#"
+            import { Id as UserId } from "./types";
+            import type { Count } from "../lib/index.js";
+            import * as Types from "./types";
+            function test(a: UserId, b: Count, c: Types.Id, d: Types.Missing) {}"#,
+            &[
+                (
+                    "src/types.ts",
+                    r#"export type Id = string; export { Count as Total } from "../lib";"#,
+                ),
+                ("lib/index.ts", r#"export * from "./count";"#),
+                ("lib/count.ts", r#"type Count = number; export { Count };"#),
+            ],
+        );
+        assert_eq!(params[0].act_type, TypeAct::String);
+        assert_eq!(params[1].act_type, TypeAct::Number);
+        assert_eq!(params[2].act_type, TypeAct::String);
+        assert_eq!(
+            params[3].act_type,
+            TypeAct::Reference("Types.Missing".to_string(), vec![])
+        );
+    }
+
+    #[test]
+    fn interface_type_test() {
+        let params = get_first_function_params(
+            r#"
+            interface Entity { id: string; }
+            interface User extends Entity { name: string; }
+            interface User { age?: number; parent?: User }
+            function test(a: User) {}"#,
+        );
+        assert_eq!(
+            params[0].act_type,
+            TypeAct::Object(vec![
+                get_property("id", TypeAct::String, false),
+                get_property("name", TypeAct::String, false),
+                get_property("age", TypeAct::Number, true),
+                get_property(
+                    "parent",
+                    TypeAct::Unchecked("recursive interface User -> User".to_string()),
+                    true
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn class_type_test() {
+        let params = get_first_function_params_in_project(
+            r#"
+            import { Admin } from "./admin";
+            import type { Guest } from "./admin";
+            import * as Admins from "./admin";
+            import { AnyAdmin } from "./admin";
+            class User {}
+            function test(a: User, b: Admin, c: Guest, d: Admins.Admin, e: AnyAdmin) {}"#,
+            &[(
+                "src/admin.ts",
+                r#"export class Admin {} export class Guest {} export type AnyAdmin = Admin;"#,
+            )],
+        );
+        assert_eq!(params[0].act_type, TypeAct::Class("User".to_string()));
+        assert_eq!(params[1].act_type, TypeAct::Class("Admin".to_string()));
+        assert_eq!(
+            params[2].act_type,
+            TypeAct::Unchecked("class Guest is imported with `import type`".to_string())
+        );
+        assert_eq!(
+            params[3].act_type,
+            TypeAct::Class("Admins.Admin".to_string())
+        );
+        assert_eq!(
+            params[4].act_type,
+            TypeAct::Unchecked("class Admin is not in scope of src/test.ts".to_string())
+        );
+    }
+
+    #[test]
+    fn builtin_class_type_test() {
+        let params = get_first_function_params(
+            r#"function test(a: Date, b: Map<string, number[]>, c: ReadonlySet<bigint>, d: Promise<void>, e: URL) {}"#,
+        );
+        assert_eq!(params[0].act_type, TypeAct::Date);
+        assert_eq!(
+            params[1].act_type,
+            TypeAct::Map(
+                Box::new(TypeAct::String),
+                Box::new(TypeAct::Array(Box::new(TypeAct::Number)))
+            )
+        );
+        assert_eq!(params[2].act_type, TypeAct::Set(Box::new(TypeAct::BigInt)));
+        assert_eq!(
+            params[3].act_type,
+            TypeAct::Promise(Box::new(TypeAct::Void))
+        );
+        assert_eq!(params[4].act_type, TypeAct::URL);
+    }
+
+    #[test]
+    fn enum_type_test() {
+        let params = get_first_function_params(
+            r#"
+            enum Direction { Up, Down = 4, Left, Right = Left * 2 }
+            const enum Color { Red = "red", Green = `green` }
+            enum Flags { A = 1 << 0, B = 1 << 1, AB = A | Flags.B }
+            declare enum Computed { A = "a".length }
+            function test(a: Direction, b: Color, c: Flags, d: Color.Green, e: Computed) {}
+            "#,
+        );
+        let get_union = |values: Vec<LiteralAct>| {
+            TypeAct::Union(values.into_iter().map(TypeAct::Literal).collect())
+        };
+        assert_eq!(
+            params[0].act_type,
+            get_union(vec![
+                LiteralAct::Number(0.0),
+                LiteralAct::Number(4.0),
+                LiteralAct::Number(5.0),
+                LiteralAct::Number(10.0),
+            ])
+        );
+        assert_eq!(
+            params[1].act_type,
+            get_union(vec![
+                LiteralAct::String("red".to_string()),
+                LiteralAct::String("green".to_string()),
+            ])
+        );
+        assert_eq!(
+            params[2].act_type,
+            get_union(vec![
+                LiteralAct::Number(1.0),
+                LiteralAct::Number(2.0),
+                LiteralAct::Number(3.0),
+            ])
+        );
+        assert_eq!(
+            params[3].act_type,
+            TypeAct::Literal(LiteralAct::String("green".to_string()))
+        );
+        assert_eq!(
+            params[4].act_type,
+            TypeAct::Unchecked("enum member Computed.A has a computed value".to_string())
+        );
+    }
+
+    #[test]
+    fn keyword_type_test() {
+        let params = get_first_function_params(
+            r#"function test(a: void, b: never, c: object, d: Function, e: any, f: unknown) {}"#,
+        );
+        assert_eq!(params[0].act_type, TypeAct::Void);
+        assert_eq!(params[1].act_type, TypeAct::Never);
+        assert_eq!(params[2].act_type, TypeAct::NonPrimitive);
+        assert_eq!(params[3].act_type, TypeAct::Function);
+        assert_eq!(params[4].act_type, TypeAct::Any("any".to_string()));
+        assert_eq!(params[5].act_type, TypeAct::Any("unknown".to_string()));
+    }
+
+    #[test]
+    fn function_type_test() {
+        let params = get_first_function_params(
+            r#"
+            interface Handler { (this: Window, event: string, ...rest: number[]): boolean }
+            function test(a: (x: number, y?: string) => void, b: Handler, c: { (): void; (x: number): void }, d: new () => object) {}
+            "#,
+        );
+        assert_eq!(
+            params[0].act_type,
+            TypeAct::Signature(
+                vec![
+                    TupleElementAct::Required(TypeAct::Number),
+                    TupleElementAct::Optional(TypeAct::String),
+                ],
+                Box::new(TypeAct::Void)
+            )
+        );
+        assert_eq!(
+            params[1].act_type,
+            TypeAct::Signature(
+                vec![
+                    TupleElementAct::Required(TypeAct::String),
+                    TupleElementAct::Rest(TypeAct::Number),
+                ],
+                Box::new(TypeAct::Boolean)
+            )
+        );
+        assert_eq!(params[2].act_type, TypeAct::Function);
+        assert_eq!(params[3].act_type, TypeAct::Function);
+    }
+
+    #[test]
+    fn generic_type_test() {
+        let params = get_first_function_params(
+            r#"
+            type Box<T, U = T[]> = { value: T; values: U };
+            function test<T extends string | number, U, V extends T[]>(a: T, b: U, c: U[], d: V, e: Box<boolean>) {}
+            "#,
+        );
+        let constraint = TypeAct::Union(vec![TypeAct::String, TypeAct::Number]);
+        let unconstrained = TypeAct::Unchecked("type parameter U has no constraint".to_string());
+        assert_eq!(params[0].act_type, constraint);
+        assert_eq!(params[1].act_type, unconstrained);
+        assert_eq!(params[2].act_type, TypeAct::Array(Box::new(unconstrained)));
+        assert_eq!(params[3].act_type, TypeAct::Array(Box::new(constraint)));
+        assert_eq!(
+            params[4].act_type,
+            TypeAct::Object(vec![
+                get_property("value", TypeAct::Boolean, false),
+                get_property("values", TypeAct::Array(Box::new(TypeAct::Boolean)), false),
+            ])
+        );
+    }
+
+    #[test]
+    fn utility_type_test() {
+        let params = get_first_function_params(
+            r#"
+            interface User { id: number; name?: string; email: string }
+            function test(a: Record<string, number>, b: Record<"x" | "y", boolean>, c: Partial<User>, d: Required<Pick<User, "name">>, e: Omit<User, "id" | "email">, f: NonNullable<string | null | undefined>, g: { [key: string]: bigint }) {}
+            "#,
+        );
+        assert_eq!(
+            params[0].act_type,
+            TypeAct::Record(Box::new(TypeAct::Number))
+        );
+        assert_eq!(
+            params[1].act_type,
+            TypeAct::Object(vec![
+                get_property("x", TypeAct::Boolean, false),
+                get_property("y", TypeAct::Boolean, false),
+            ])
+        );
+        assert_eq!(
+            params[2].act_type,
+            TypeAct::Object(vec![
+                get_property("id", TypeAct::Number, true),
+                get_property("name", TypeAct::String, true),
+                get_property("email", TypeAct::String, true),
+            ])
+        );
+        assert_eq!(
+            params[3].act_type,
+            TypeAct::Object(vec![get_property("name", TypeAct::String, false)])
+        );
+        assert_eq!(
+            params[4].act_type,
+            TypeAct::Object(vec![get_property("name", TypeAct::String, true)])
+        );
+        assert_eq!(params[5].act_type, TypeAct::Union(vec![TypeAct::String]));
+        assert_eq!(
+            params[6].act_type,
+            TypeAct::Record(Box::new(TypeAct::BigInt))
+        );
+    }
+
+    #[test]
+    fn template_literal_type_test() {
+        let params = get_first_function_params(
+            r#"function test(a: `user_${string}`, b: `${number}px`, c: `plain`) {}"#,
+        );
+        assert_eq!(
+            params[0].act_type,
+            TypeAct::TemplateLiteral(vec![
+                TemplatePartAct::Text("user_".to_string()),
+                TemplatePartAct::Placeholder(TypeAct::String),
+            ])
+        );
+        assert_eq!(
+            params[1].act_type,
+            TypeAct::TemplateLiteral(vec![
+                TemplatePartAct::Placeholder(TypeAct::Number),
+                TemplatePartAct::Text("px".to_string()),
+            ])
+        );
+        assert_eq!(
+            params[2].act_type,
+            TypeAct::Literal(LiteralAct::String("plain".to_string()))
+        );
+    }
+
+    #[test]
+    fn intersection_type_test() {
+        let params = get_first_function_params(
+            r#"
+            type Email = string & { __brand: "Email" };
+            function test(a: { id: number } & { name: string }, b: Email, c: number[] & { length: 2 }) {}
+            "#,
+        );
+        assert_eq!(
+            params[0].act_type,
+            TypeAct::Object(vec![
+                get_property("id", TypeAct::Number, false),
+                get_property("name", TypeAct::String, false),
+            ])
+        );
+        assert_eq!(params[1].act_type, TypeAct::String);
+        assert_eq!(
+            params[2].act_type,
+            TypeAct::Intersection(vec![
+                TypeAct::Array(Box::new(TypeAct::Number)),
+                TypeAct::Object(vec![get_property(
+                    "length",
+                    TypeAct::Literal(LiteralAct::Number(2.0)),
+                    false
+                )]),
+            ])
+        );
+    }
+
+    #[test]
+    fn optional_param_test() {
+        let params = get_first_function_params(r#"function test(a: number, b?: number) {}"#);
+        assert!(!params[0].optional);
+        assert!(params[1].optional);
+        assert_eq!(params[1].act_type, TypeAct::Number);
+    }
+
+    #[test]
+    fn default_param_test() {
+        let params =
+            get_first_function_params(r#"function test(a: string = "d", b = 1, c = compute()) {}"#);
+        assert_eq!(params[0].name, "a");
+        assert_eq!(params[0].act_type, TypeAct::String);
+        assert_eq!(params[1].name, "b");
+        assert_eq!(params[1].act_type, TypeAct::Unknown);
+        assert_eq!(params[1].default_type, Some(TypeAct::Number));
+        assert_eq!(params[2].default_type, None);
+    }
+
+    #[test]
+    fn destructured_param_test() {
+        let params = get_first_function_params(
+            r#"function test({ id, name }: { id: number; name: string }, [x, y]: [number, number] = [0, 0], { z } = {}) {}"#,
+        );
+        assert_eq!(params[0].name, "__act_param_0");
+        assert_eq!(
+            params[0].destructure.as_ref().unwrap().pattern,
+            "{ id, name }"
+        );
+        assert_eq!(params[0].destructure.as_ref().unwrap().byte_pos, 14);
+        assert_eq!(params[1].name, "__act_param_1");
+        assert_eq!(params[1].destructure.as_ref().unwrap().pattern, "[x, y]");
+        assert_eq!(
+            params[1].act_type,
+            TypeAct::Tuple(vec![
+                TupleElementAct::Required(TypeAct::Number),
+                TupleElementAct::Required(TypeAct::Number),
+            ])
+        );
+        assert_eq!(params[2].destructure.as_ref().unwrap().pattern, "{ z }");
+        let params = get_first_function_params(r#"function test({ a }: { a: number }, b = a) {}"#);
+        assert!(params[0].destructure.is_none());
+    }
+
+    #[test]
+    fn rest_param_test() {
+        let params =
+            get_first_function_params(r#"function test(a: string, ...values: number[]) {}"#);
+        assert!(!params[0].rest);
+        assert!(params[1].rest);
+        assert_eq!(params[1].name, "values");
+        assert_eq!(
+            params[1].act_type,
+            TypeAct::Array(Box::new(TypeAct::Number))
+        );
+    }
+
+    #[test]
+    fn constructor_param_prop_test() {
+        let source = r#"class A extends B {
+    constructor(private name: string, public count: number = 1) {
+        super();
+    }
+}"#;
+        let module_items = parse_module_items(source);
+        let file_types = get_file_types_act(&module_items);
+        let project_types = ProjectTypesAct::default();
+        let args = default_args();
+        let scope = &ActScope::new(
+            &file_types,
+            Path::new("src/test.ts"),
+            source,
+            &project_types,
+            &args,
+        );
+        let class_decl = module_items
+            .into_iter()
+            .find_map(|item| item.stmt()?.decl()?.class())
+            .unwrap();
+        let class_act = get_class_act(class_decl.ident.sym.to_string(), &class_decl.class, scope);
+        let constructor = &class_act.methods[0].function;
+        assert_eq!(constructor.params[0].name, "name");
+        assert_eq!(constructor.params[0].act_type, TypeAct::String);
+        assert_eq!(constructor.params[1].name, "count");
+        assert_eq!(constructor.params[1].act_type, TypeAct::Number);
+        assert!(constructor.params[0].property);
+        // Right after `super();`
+        assert_eq!(
+            class_act.methods[0].super_call_end.unwrap() as usize,
+            source.find("super();").unwrap() + "super();".len()
+        );
+    }
+
+    #[test]
+    fn derived_constructor_patch_test() {
+        // Destructured before `super()` which uses the bound name, checked after it
+        let source = r#"class A extends B {
+    constructor({ a }: { a: number }, private b: string) {
+        super(a);
+    }
+}"#;
+        let patched_source = get_patched_source(source);
+        let declaration_index = patched_source.find("var { a } = __act_param_0;").unwrap();
+        let super_index = patched_source.find("super(a);").unwrap();
+        let check_index = patched_source.find("if(!(typeof b === 'string'))").unwrap();
+        assert!(declaration_index < super_index && super_index < check_index);
+        assert!(patched_source.contains("constructor(__act_param_0: { a: number }"));
+        // Without parameter properties nor initialized fields the arguments are checked
+        // before they reach `super()`
+        let source = r#"class A extends B {
+    declare c: number;
+    static d = 1;
+    constructor(b: string) {
+        super(b);
+    }
+}"#;
+        let patched_source = get_patched_source(source);
+        assert!(
+            patched_source.find("if(!(typeof b === 'string'))").unwrap()
+                < patched_source.find("super(b);").unwrap()
+        );
+    }
+
+    #[test]
+    fn callback_param_prop_patch_test() {
+        let args = ActArgs::parse_from(["actypes", "--wrap-callbacks"]);
+        let source = r#"class A {
+    constructor(private cb: (x: number) => string, other: (x: number) => string) {}
+}"#;
+        let module_items = parse_module_items(source);
+        let patches = process_module_items(
+            module_items,
+            Path::new("src/test.ts"),
+            source,
+            &ProjectTypesAct::default(),
+            &args,
+        )
+        .unwrap();
+        let patched_source =
+            String::from_utf8(get_patched_buffer(source.as_bytes().to_vec(), patches)).unwrap();
+        assert!(patched_source.contains("})(cb);\n    this.cb = cb;"));
+        assert!(!patched_source.contains("this.other"));
+    }
+
+    #[test]
+    fn body_start_test() {
+        let get_first_body = |source: &str| {
+            parse_module_items(source)
+                .into_iter()
+                .find_map(|item| item.stmt()?.decl()?.fn_decl())
+                .unwrap()
+                .function
+                .body
+                .unwrap()
+        };
+        let source = r#"function test() { return; }"#;
+        assert_eq!(
+            get_body_start(&get_first_body(source)) as usize,
+            source.find('{').unwrap() + 1
+        );
+        let source = r#"function test() { "use strict"; 'use asm'; return; }"#;
+        assert_eq!(
+            get_body_start(&get_first_body(source)) as usize,
+            source.find("'use asm';").unwrap() + "'use asm';".len()
+        );
+        // Not a directive once another statement precedes it
+        let source = r#"function test() { call(); "use strict"; }"#;
+        assert_eq!(
+            get_body_start(&get_first_body(source)) as usize,
+            source.find('{').unwrap() + 1
+        );
+    }
+
+    #[test]
+    fn enum_member_fix_patch_test() {
+        let args = ActArgs::parse_from(["actypes", "fix"]);
+        let params = get_first_function_params(
+            r#"
+            enum Color { Red = "red", Blue = "blue" }
+            function test(r: Color.Red) {}
+            "#,
+        );
+        let param = params.into_iter().next().unwrap();
+        assert_eq!(
+            param.act_type,
+            TypeAct::Literal(LiteralAct::String("red".to_string()))
+        );
+        let type_check = gen_type_check(&param.act_type, &param.name, 0, &args).unwrap();
+        let patch = gen_param_type_check_patch(param, type_check, "test", "a.ts", &args);
+        assert!(!patch.contains("===(r)"));
+        assert!(patch.contains(r#"console.warn(`[a.ts=>test] r isn't one of "red" but is"#));
+    }
+
+    #[test]
+    fn shadowed_destructured_param_test() {
+        let params = get_first_function_params(
+            r#"function test({ a }: { a: string }, [b]: [number]) { function a() {} return a; }"#,
+        );
+        assert!(params[0].destructure.is_none());
+        assert_eq!(params[0].name, "{ a }");
+        assert_eq!(
+            params[0].act_type,
+            TypeAct::Unchecked(
+                "destructured parameter is shadowed by a function declaration".to_string()
+            )
+        );
+        assert!(params[1].destructure.is_some());
+        let params = get_first_function_params(r#"function test({ z } = {}) {}"#);
+        assert_eq!(params[0].act_type, TypeAct::Unknown);
+    }
+
+    #[test]
+    fn unchecked_destructured_param_name_test() {
+        let params = get_first_function_params(
+            r#"function test({ a }: { a: number }, b = a, ...[c, d]: [string, string]) {}"#,
+        );
+        assert_eq!(params[0].name, "{ a }");
+        assert_eq!(
+            params[0].act_type,
+            TypeAct::Unchecked("destructured parameter is followed by a default value".to_string())
+        );
+        assert_eq!(params[2].name, "[c, d]");
+        assert_eq!(
+            params[2].act_type,
+            TypeAct::Unchecked("destructured rest parameter".to_string())
+        );
+    }
+
+    #[test]
+    fn expression_arrow_patch_test() {
+        assert_eq!(
+            get_patched_source("const double = (x: number) => x * 2;"),
+            format!(
+                "const double = (x: number) => {{{}return x * 2; }};",
+                get_typeof_check("double", "x", "number")
+            )
+        );
+        // The parentheses stay around the object literal so it isn't read as a block
+        let patched_source = get_patched_source("const wrap = (o: {a: number}) => ({ a: o.a });");
+        assert!(patched_source.starts_with("const wrap = (o: {a: number}) => {\n    if(!("));
+        assert!(patched_source.ends_with("return ({ a: o.a }); };"));
+        // Both arrows end at the same offset, the inner block is closed first
+        assert_eq!(
+            get_patched_source("const add = (n: number) => (m: number) => m + n;"),
+            format!(
+                "const add = (n: number) => {{{}return (m: number) => {{{}return m + n; }}; }};",
+                get_typeof_check("add", "n", "number"),
+                get_typeof_check("AnonymousFunction", "m", "number")
+            )
+        );
+        assert_eq!(
+            get_patched_source("const pick = flag ? (a: string) => a : (b: number) => b;"),
+            format!(
+                "const pick = flag ? (a: string) => {{{}return a; }} : (b: number) => {{{}return b; }};",
+                get_typeof_check("AnonymousFunction", "a", "string"),
+                get_typeof_check("AnonymousFunction", "b", "number")
+            )
+        );
+        // Nothing to check, the body is left as is
+        assert_eq!(
+            get_patched_source("const same = (x) => x;"),
+            "const same = (x) => x;"
+        );
+    }
+
+    #[test]
+    fn nested_functions_patch_test() {
+        let source = r#"
+        if (flag) {
+            function inIf(a: number) {}
+        } else {
+            const inElse = (b: string) => {};
+        }
+        for (let i = 0; i < 1; i++) {
+            const inFor = function (c: boolean) {};
+        }
+        for (const item of items) {
+            items.map((d: number) => { return d; });
+        }
+        while (flag) {
+            function inWhile(e: string) {}
+        }
+        try {
+            function inTry(f: number) {}
+        } catch (err) {
+            function inCatch(g: number) {}
+        } finally {
+            function inFinally(h: number) {}
+        }
+        class Service {
+            run() {
+                const inMethod = (i: string) => {};
+            }
+            handler = (j: number) => {};
+            static {
+                function inStaticBlock(k: number) {}
+            }
+        }
+        const object = {
+            method(l: string) {},
+            get getter() {
+                return (m: number) => {};
+            },
+            set setter(n: number) {},
+        };
+        function factory(o: number) {
+            return function (p: string) {
+                return (q: boolean) => {};
+            };
+        }
+        function withDefault(callback = (r: number) => {}) {}
+        "#;
+        let patched_source = get_patched_source(source);
+        for (symbol_name, name, ts_type) in [
+            ("inIf", "a", "number"),
+            ("inElse", "b", "string"),
+            ("inFor", "c", "boolean"),
+            ("AnonymousFunction", "d", "number"),
+            ("inWhile", "e", "string"),
+            ("inTry", "f", "number"),
+            ("inCatch", "g", "number"),
+            ("inFinally", "h", "number"),
+            ("inMethod", "i", "string"),
+            ("handler", "j", "number"),
+            ("inStaticBlock", "k", "number"),
+            ("method", "l", "string"),
+            ("AnonymousFunction", "m", "number"),
+            ("setter", "n", "number"),
+            ("factory", "o", "number"),
+            ("AnonymousFunction", "p", "string"),
+            ("AnonymousFunction", "q", "boolean"),
+            ("AnonymousFunction", "r", "number"),
+        ] {
+            assert_eq!(
+                patched_source
+                    .matches(&get_typeof_check(symbol_name, name, ts_type))
+                    .count(),
+                1,
+                "{} is not checked once in {}",
+                name,
+                symbol_name
+            );
+        }
+    }
+
+    #[test]
+    fn function_default_destructured_param_test() {
+        let source = r#"function test({ callback = (x: number) => {} }: { callback?: (x: number) => void }) {}"#;
+        let params = get_first_function_params(source);
+        assert!(params[0].destructure.is_none());
+        assert_eq!(params[0].name, "{ callback = (x: number) => {} }");
+        // The pattern is left in place so the default value is checked where it is declared
+        assert_eq!(
+            get_patched_source(source),
+            format!(
+                "function test({{ callback = (x: number) => {{{}}} }}: {{ callback?: (x: number) => void }}) {{}}",
+                get_typeof_check("AnonymousFunction", "x", "number")
+            )
+        );
+    }
+}
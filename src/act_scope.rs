@@ -0,0 +1,392 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+use swc_ecma_ast::{
+    ClassExpr, Decl, DefaultDecl, ExportSpecifier, ImportSpecifier, ModuleDecl, ModuleExportName,
+    ModuleItem, TsEnumDecl, TsInterfaceDecl, TsTypeAliasDecl,
+};
+
+use crate::act_structs::TypeAct;
+use crate::args_parser::ActArgs;
+
+#[derive(Debug, Clone)]
+pub struct ImportAct {
+    pub src: String,
+    // Name exported by `src`, "default" for default imports and "*" for namespace imports
+    pub imported: String,
+    pub type_only: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum ExportAct {
+    // `export type A = ...` or `export { a as b }`, holds the local name
+    Local(String),
+    // `export { a as b } from "./mod"`, holds the module and its export name
+    From(String, String),
+}
+
+// Type declarations of a file that parameter types can refer to
+#[derive(Debug, Default)]
+pub struct FileTypesAct {
+    pub type_aliases: HashMap<String, TsTypeAliasDecl>,
+    pub interfaces: HashMap<String, Vec<TsInterfaceDecl>>,
+    pub enums: HashMap<String, TsEnumDecl>,
+    pub classes: HashSet<String>,
+    pub imports: HashMap<String, ImportAct>,
+    pub exports: HashMap<String, ExportAct>,
+    // Modules re-exported with `export * from "./mod"`
+    pub star_exports: Vec<String>,
+}
+
+// Type declarations of every file processed, indexed by normalized path
+#[derive(Debug, Default)]
+pub struct ProjectTypesAct {
+    pub files: HashMap<PathBuf, FileTypesAct>,
+}
+
+pub enum TypeDeclAct<'a> {
+    Alias(&'a TsTypeAliasDecl),
+    Interface(&'a Vec<TsInterfaceDecl>),
+    Enum(&'a TsEnumDecl),
+    Class,
+}
+
+#[derive(Clone)]
+pub struct ActScope<'a> {
+    pub file_types: &'a FileTypesAct,
+    pub file_path: PathBuf,
+    // File the checks are inserted in, the only one whose bindings are in scope
+    pub origin_file_path: PathBuf,
+    // Source of the origin file, destructured parameters are copied from it
+    pub origin_source: &'a str,
+    pub project_types: &'a ProjectTypesAct,
+    // Types being resolved with the file they are declared in, used to detect cycles
+    pub resolving: Vec<(PathBuf, String)>,
+    // Type parameters in scope with the type they are checked as
+    pub type_params: HashMap<String, TypeAct>,
+    // Options of the run, the checks are generated according to them
+    pub args: &'a ActArgs,
+}
+
+impl<'a> ActScope<'a> {
+    pub fn new(
+        file_types: &'a FileTypesAct,
+        file_path: &Path,
+        source: &'a str,
+        project_types: &'a ProjectTypesAct,
+        args: &'a ActArgs,
+    ) -> ActScope<'a> {
+        ActScope {
+            file_types,
+            file_path: normalize_path(file_path),
+            origin_file_path: normalize_path(file_path),
+            origin_source: source,
+            project_types,
+            resolving: vec![],
+            type_params: HashMap::new(),
+            args,
+        }
+    }
+
+    pub fn with_resolving(&self, type_name: &str) -> ActScope<'a> {
+        let mut scope = self.clone();
+        scope
+            .resolving
+            .push((self.file_path.clone(), type_name.to_string()));
+        scope
+    }
+
+    pub fn with_type_params(&self, type_params: HashMap<String, TypeAct>) -> ActScope<'a> {
+        let mut scope = self.clone();
+        scope.type_params.extend(type_params);
+        scope
+    }
+
+    // Scope a declaration of the current file is resolved in, outer type parameters don't apply
+    pub fn get_decl_scope(&self) -> ActScope<'a> {
+        let mut scope = self.clone();
+        scope.type_params.clear();
+        scope
+    }
+
+    pub fn get_cycle(&self, type_name: &str) -> Option<String> {
+        let cycle_start = self
+            .resolving
+            .iter()
+            .position(|(file_path, resolving_name)| {
+                *file_path == self.file_path && resolving_name == type_name
+            })?;
+        let mut cycle: Vec<String> = self.resolving[cycle_start..]
+            .iter()
+            .map(|(_, resolving_name)| resolving_name.to_string())
+            .collect();
+        cycle.push(type_name.to_string());
+        Some(cycle.join(" -> "))
+    }
+
+    // Scope of the file `src` refers to when imported from the current file
+    pub fn get_module_scope(&self, src: &str) -> Option<ActScope<'a>> {
+        let module_path = resolve_module_path(&self.file_path, src, self.project_types)?;
+        Some(ActScope {
+            file_types: self.project_types.files.get(&module_path)?,
+            file_path: module_path,
+            origin_file_path: self.origin_file_path.clone(),
+            origin_source: self.origin_source,
+            project_types: self.project_types,
+            resolving: self.resolving.clone(),
+            type_params: HashMap::new(),
+            args: self.args,
+        })
+    }
+
+    pub fn find_type_decl(&self, type_name: &str) -> Option<(TypeDeclAct<'a>, ActScope<'a>)> {
+        if let Some(type_alias_decl) = self.file_types.type_aliases.get(type_name) {
+            return Some((TypeDeclAct::Alias(type_alias_decl), self.get_decl_scope()));
+        }
+        if self.file_types.classes.contains(type_name) {
+            return Some((TypeDeclAct::Class, self.get_decl_scope()));
+        }
+        if let Some(interface_decls) = self.file_types.interfaces.get(type_name) {
+            return Some((
+                TypeDeclAct::Interface(interface_decls),
+                self.get_decl_scope(),
+            ));
+        }
+        if let Some(enum_decl) = self.file_types.enums.get(type_name) {
+            return Some((TypeDeclAct::Enum(enum_decl), self.get_decl_scope()));
+        }
+        let import = self.file_types.imports.get(type_name)?;
+        self.get_module_scope(&import.src)?
+            .find_exported_type_decl(&import.imported, &mut vec![])
+    }
+
+    pub fn find_exported_type_decl(
+        &self,
+        export_name: &str,
+        visited: &mut Vec<PathBuf>,
+    ) -> Option<(TypeDeclAct<'a>, ActScope<'a>)> {
+        // Re-exports can form cycles between modules
+        if visited.contains(&self.file_path) {
+            return None;
+        }
+        visited.push(self.file_path.clone());
+        match self.file_types.exports.get(export_name) {
+            Some(ExportAct::Local(local_name)) => self.find_type_decl(local_name),
+            Some(ExportAct::From(src, imported)) => self
+                .get_module_scope(src)?
+                .find_exported_type_decl(imported, visited),
+            None => self.file_types.star_exports.iter().find_map(|src| {
+                self.get_module_scope(src)?
+                    .find_exported_type_decl(export_name, visited)
+            }),
+        }
+    }
+
+    pub fn is_origin_file(&self) -> bool {
+        self.file_path == self.origin_file_path
+    }
+
+    pub fn is_type_only_import(&self, local_name: &str) -> bool {
+        match self.file_types.imports.get(local_name) {
+            Some(import) => import.type_only,
+            None => false,
+        }
+    }
+
+    // Resolve `Namespace.Type` where `Namespace` comes from `import * as Namespace`
+    pub fn find_namespace_type_decl(
+        &self,
+        namespace: &str,
+        type_name: &str,
+    ) -> Option<(TypeDeclAct<'a>, ActScope<'a>)> {
+        let import = self.file_types.imports.get(namespace)?;
+        if import.imported != "*" {
+            return None;
+        }
+        self.get_module_scope(&import.src)?
+            .find_exported_type_decl(type_name, &mut vec![])
+    }
+}
+
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized_path = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized_path.pop() {
+                    normalized_path.push("..");
+                }
+            }
+            _ => normalized_path.push(component),
+        }
+    }
+    normalized_path
+}
+
+pub fn resolve_module_path(
+    file_path: &Path,
+    src: &str,
+    project_types: &ProjectTypesAct,
+) -> Option<PathBuf> {
+    // Only relative imports can point to a file of the project
+    if !src.starts_with("./") && !src.starts_with("../") {
+        return None;
+    }
+    let module_path = file_path.parent()?.join(src);
+    let module_path_str = module_path.to_str()?;
+    let mut candidates = vec![
+        module_path.clone(),
+        PathBuf::from(format!("{}.ts", module_path_str)),
+        PathBuf::from(format!("{}.d.ts", module_path_str)),
+        module_path.join("index.ts"),
+    ];
+    // ESM style imports use the extension of the emitted file
+    if let Some(module_path_without_ext) = module_path_str.strip_suffix(".js") {
+        candidates.push(PathBuf::from(format!("{}.ts", module_path_without_ext)));
+    }
+    candidates
+        .iter()
+        .map(|candidate| normalize_path(candidate))
+        .find(|candidate| project_types.files.contains_key(candidate))
+}
+
+pub fn get_module_export_name(module_export_name: &ModuleExportName) -> String {
+    match module_export_name {
+        ModuleExportName::Ident(ident) => ident.sym.to_string(),
+        ModuleExportName::Str(str_lit) => str_lit.value.to_string(),
+    }
+}
+
+pub fn register_decl(file_types: &mut FileTypesAct, decl: &Decl) -> Option<String> {
+    match decl {
+        Decl::TsTypeAlias(type_alias_decl) => {
+            let type_alias_name = type_alias_decl.id.sym.to_string();
+            file_types
+                .type_aliases
+                .insert(type_alias_name.clone(), *type_alias_decl.clone());
+            Some(type_alias_name)
+        }
+        Decl::TsInterface(interface_decl) => {
+            let interface_name = interface_decl.id.sym.to_string();
+            file_types
+                .interfaces
+                .entry(interface_name.clone())
+                .or_default()
+                .push(*interface_decl.clone());
+            Some(interface_name)
+        }
+        Decl::TsEnum(enum_decl) => {
+            let enum_name = enum_decl.id.sym.to_string();
+            file_types
+                .enums
+                .insert(enum_name.clone(), *enum_decl.clone());
+            Some(enum_name)
+        }
+        Decl::Class(class_decl) => {
+            let class_name = class_decl.ident.sym.to_string();
+            file_types.classes.insert(class_name.clone());
+            Some(class_name)
+        }
+        _ => None,
+    }
+}
+
+pub fn register_module_decl(file_types: &mut FileTypesAct, module_decl: &ModuleDecl) {
+    match module_decl {
+        ModuleDecl::Import(import_decl) => {
+            let src = import_decl.src.value.to_string();
+            for specifier in &import_decl.specifiers {
+                let (local, import) = match specifier {
+                    ImportSpecifier::Named(named_specifier) => (
+                        &named_specifier.local,
+                        ImportAct {
+                            src: src.clone(),
+                            imported: match &named_specifier.imported {
+                                Some(imported) => get_module_export_name(imported),
+                                None => named_specifier.local.sym.to_string(),
+                            },
+                            type_only: import_decl.type_only || named_specifier.is_type_only,
+                        },
+                    ),
+                    ImportSpecifier::Default(default_specifier) => (
+                        &default_specifier.local,
+                        ImportAct {
+                            src: src.clone(),
+                            imported: "default".to_string(),
+                            type_only: import_decl.type_only,
+                        },
+                    ),
+                    ImportSpecifier::Namespace(namespace_specifier) => (
+                        &namespace_specifier.local,
+                        ImportAct {
+                            src: src.clone(),
+                            imported: "*".to_string(),
+                            type_only: import_decl.type_only,
+                        },
+                    ),
+                };
+                file_types.imports.insert(local.sym.to_string(), import);
+            }
+        }
+        ModuleDecl::ExportDecl(export_decl) => {
+            if let Some(decl_name) = register_decl(file_types, &export_decl.decl) {
+                file_types
+                    .exports
+                    .insert(decl_name.clone(), ExportAct::Local(decl_name));
+            }
+        }
+        ModuleDecl::ExportDefaultDecl(export_default_decl) => {
+            if let DefaultDecl::Class(ClassExpr {
+                ident: Some(class_ident),
+                ..
+            }) = &export_default_decl.decl
+            {
+                let class_name = class_ident.sym.to_string();
+                file_types.classes.insert(class_name.clone());
+                file_types
+                    .exports
+                    .insert("default".to_string(), ExportAct::Local(class_name));
+            }
+        }
+        ModuleDecl::ExportNamed(named_export) => {
+            for specifier in &named_export.specifiers {
+                if let ExportSpecifier::Named(named_specifier) = specifier {
+                    let orig = get_module_export_name(&named_specifier.orig);
+                    let exported = match &named_specifier.exported {
+                        Some(exported) => get_module_export_name(exported),
+                        None => orig.clone(),
+                    };
+                    let export = match &named_export.src {
+                        Some(src) => ExportAct::From(src.value.to_string(), orig),
+                        None => ExportAct::Local(orig),
+                    };
+                    file_types.exports.insert(exported, export);
+                }
+            }
+        }
+        ModuleDecl::ExportAll(export_all) => {
+            file_types
+                .star_exports
+                .push(export_all.src.value.to_string());
+        }
+        _ => {}
+    }
+}
+
+pub fn get_file_types_act(module_items: &[ModuleItem]) -> FileTypesAct {
+    let mut file_types = FileTypesAct::default();
+    for item in module_items {
+        match item {
+            ModuleItem::Stmt(stmt) => {
+                if let Some(decl) = stmt.as_decl() {
+                    register_decl(&mut file_types, decl);
+                }
+            }
+            ModuleItem::ModuleDecl(module_decl) => {
+                register_module_decl(&mut file_types, module_decl);
+            }
+        }
+    }
+    file_types
+}
@@ -2,6 +2,8 @@
 pub struct PatchAct {
     pub byte_pos: u32,
     pub patch: Vec<u8>,
+    // Number of bytes from `byte_pos` the patch replaces, 0 for an insertion
+    pub replaced_len: u32,
 }
 
 #[derive(Debug)]
@@ -14,6 +16,9 @@ pub struct FunctionAct {
 #[derive(Debug)]
 pub struct MethodAct {
     pub function: FunctionAct,
+    // End of the top level `super()` call when the checks have to follow it,
+    // destructured parameters are still declared at the body start
+    pub super_call_end: Option<u32>,
 }
 
 #[derive(Debug)]
@@ -22,13 +27,24 @@ pub struct ClassAct {
     pub methods: Vec<MethodAct>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum TypeAct {
     Number,
     String,
     BigInt,
     Boolean,
     Symbol,
+    Null,
+    Undefined,
+    Void,
+    Never,
+    // The `object` keyword, any non-primitive value
+    NonPrimitive,
+    Function,
+    // Function with its parameters typed as a tuple and its return type
+    Signature(Vec<TupleElementAct>, Box<TypeAct>),
+    // `any` or `unknown`, holds the keyword
+    Any(String),
     Float32Array,
     Float64Array,
     Int8Array,
@@ -40,12 +56,99 @@ pub enum TypeAct {
     Uint32Array,
     BigInt64Array,
     BigUint64Array,
+    Date,
+    RegExp,
+    Error,
+    ArrayBuffer,
+    DataView,
+    URL,
+    Map(Box<TypeAct>, Box<TypeAct>),
+    Set(Box<TypeAct>),
+    Promise(Box<TypeAct>),
+    Union(Vec<TypeAct>),
+    Intersection(Vec<TypeAct>),
+    Array(Box<TypeAct>),
+    Tuple(Vec<TupleElementAct>),
+    Object(Vec<PropertyAct>),
+    // Object with an index signature, holds the type of every value
+    Record(Box<TypeAct>),
+    Literal(LiteralAct),
+    TemplateLiteral(Vec<TemplatePartAct>),
+    // A named type that isn't a builtin, with its generic arguments
+    Reference(String, Vec<TypeAct>),
+    // A class declared in the project, holds the name it is in scope with
+    Class(String),
+    // A type that can't be checked at runtime, with the reason to report
+    Unchecked(String),
     Unknown,
 }
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TupleElementAct {
+    Required(TypeAct),
+    Optional(TypeAct),
+    // Holds the type of each element captured by the rest
+    Rest(TypeAct),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LiteralAct {
+    String(String),
+    Number(f64),
+    BigInt(String),
+    Boolean(bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplatePartAct {
+    Text(String),
+    Placeholder(TypeAct),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyAct {
+    pub name: String,
+    pub act_type: TypeAct,
+    pub optional: bool,
+}
+
 #[derive(Debug)]
 pub struct ParamAct {
     pub name: String,
     pub act_type: TypeAct,
+    pub optional: bool,
+    pub rest: bool,
+    // Type of the literal default value, used when the parameter has no annotation
+    pub default_type: Option<TypeAct>,
+    pub destructure: Option<DestructureAct>,
+    // Parameter property, the constructor also assigns it to `this`
+    pub property: bool,
+}
+
+// Destructuring pattern of a parameter. The pattern is replaced by a temporary
+// so the whole argument can be checked, then destructured at the body start.
+#[derive(Debug)]
+pub struct DestructureAct {
+    pub pattern: String,
+    pub byte_pos: u32,
+}
+
+pub fn get_literal_string(literal: &LiteralAct) -> String {
+    match literal {
+        LiteralAct::String(value) => format!("{:?}", value),
+        LiteralAct::Number(value) => value.to_string(),
+        LiteralAct::BigInt(value) => format!("{}n", value),
+        LiteralAct::Boolean(value) => value.to_string(),
+    }
+}
+
+// Types whose every possible value can be listed in an error message
+pub fn is_value_set_acttype(act_type: &TypeAct) -> bool {
+    match act_type {
+        TypeAct::Literal(_) | TypeAct::Null | TypeAct::Undefined => true,
+        TypeAct::Union(types) => types.iter().all(is_value_set_acttype),
+        _ => false,
+    }
 }
 
 pub fn get_ts_type_from_acttype(act_type: &TypeAct) -> String {
@@ -55,6 +158,21 @@ pub fn get_ts_type_from_acttype(act_type: &TypeAct) -> String {
         TypeAct::BigInt => "bigint".to_string(),
         TypeAct::Boolean => "boolean".to_string(),
         TypeAct::Symbol => "symbol".to_string(),
+        TypeAct::Null => "null".to_string(),
+        TypeAct::Undefined => "undefined".to_string(),
+        TypeAct::Void => "void".to_string(),
+        TypeAct::Never => "never".to_string(),
+        TypeAct::NonPrimitive => "object".to_string(),
+        TypeAct::Function => "Function".to_string(),
+        TypeAct::Signature(params, return_type) if params.is_empty() => {
+            format!("() => {}", get_ts_type_from_acttype(return_type))
+        }
+        TypeAct::Signature(params, return_type) => format!(
+            "(...args: {}) => {}",
+            get_ts_type_from_acttype(&TypeAct::Tuple(params.clone())),
+            get_ts_type_from_acttype(return_type)
+        ),
+        TypeAct::Any(keyword) => keyword.to_string(),
         TypeAct::Float32Array => "Float32Array".to_string(),
         TypeAct::Float64Array => "Float64Array".to_string(),
         TypeAct::Int8Array => "Int8Array".to_string(),
@@ -66,6 +184,105 @@ pub fn get_ts_type_from_acttype(act_type: &TypeAct) -> String {
         TypeAct::Uint32Array => "Uint32Array".to_string(),
         TypeAct::BigInt64Array => "BigInt64Array".to_string(),
         TypeAct::BigUint64Array => "BigUint64Array".to_string(),
+        TypeAct::Date => "Date".to_string(),
+        TypeAct::RegExp => "RegExp".to_string(),
+        TypeAct::Error => "Error".to_string(),
+        TypeAct::ArrayBuffer => "ArrayBuffer".to_string(),
+        TypeAct::DataView => "DataView".to_string(),
+        TypeAct::URL => "URL".to_string(),
+        TypeAct::Map(key_type, value_type) => format!(
+            "Map<{}, {}>",
+            get_ts_type_from_acttype(key_type),
+            get_ts_type_from_acttype(value_type)
+        ),
+        TypeAct::Set(elem_type) => format!("Set<{}>", get_ts_type_from_acttype(elem_type)),
+        TypeAct::Promise(result_type) => {
+            format!("Promise<{}>", get_ts_type_from_acttype(result_type))
+        }
+        TypeAct::Union(types) => types
+            .iter()
+            .map(get_ts_type_from_acttype)
+            .collect::<Vec<String>>()
+            .join(" | "),
+        TypeAct::Intersection(types) => types
+            .iter()
+            .map(|intersection_member| match intersection_member {
+                TypeAct::Union(_) => format!("({})", get_ts_type_from_acttype(intersection_member)),
+                _ => get_ts_type_from_acttype(intersection_member),
+            })
+            .collect::<Vec<String>>()
+            .join(" & "),
+        TypeAct::Array(elem_type) => match **elem_type {
+            TypeAct::Union(_) | TypeAct::Intersection(_) => {
+                format!("({})[]", get_ts_type_from_acttype(elem_type))
+            }
+            _ => format!("{}[]", get_ts_type_from_acttype(elem_type)),
+        },
+        TypeAct::Tuple(elements) => {
+            let elements_string = elements
+                .iter()
+                .map(|element| match element {
+                    TupleElementAct::Required(act_type) => get_ts_type_from_acttype(act_type),
+                    TupleElementAct::Optional(act_type) => {
+                        format!("{}?", get_ts_type_from_acttype(act_type))
+                    }
+                    TupleElementAct::Rest(act_type) => format!(
+                        "...{}",
+                        get_ts_type_from_acttype(&TypeAct::Array(Box::new(act_type.clone())))
+                    ),
+                })
+                .collect::<Vec<String>>()
+                .join(", ");
+            format!("[{}]", elements_string)
+        }
+        TypeAct::Record(value_type) => {
+            format!("Record<string, {}>", get_ts_type_from_acttype(value_type))
+        }
+        TypeAct::TemplateLiteral(parts) => {
+            let parts_string = parts
+                .iter()
+                .map(|part| match part {
+                    TemplatePartAct::Text(text) => text.to_string(),
+                    TemplatePartAct::Placeholder(act_type) => {
+                        format!("${{{}}}", get_ts_type_from_acttype(act_type))
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join("");
+            format!("`{}`", parts_string)
+        }
+        TypeAct::Object(properties) => {
+            if properties.is_empty() {
+                return "{}".to_string();
+            }
+            let properties_string = properties
+                .iter()
+                .map(|property| {
+                    format!(
+                        "{}{}: {}",
+                        property.name,
+                        if property.optional { "?" } else { "" },
+                        get_ts_type_from_acttype(&property.act_type)
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join("; ");
+            format!("{{ {} }}", properties_string)
+        }
+        TypeAct::Literal(literal) => get_literal_string(literal),
+        TypeAct::Reference(name, type_args) => {
+            if type_args.is_empty() {
+                return name.to_string();
+            }
+            let type_args_string = type_args
+                .iter()
+                .map(get_ts_type_from_acttype)
+                .collect::<Vec<String>>()
+                .join(", ");
+            format!("{}<{}>", name, type_args_string)
+        }
+        TypeAct::Class(name) => name.to_string(),
+        TypeAct::Unchecked(_) => "unknown".to_string(),
         TypeAct::Unknown => "unknown".to_string(),
     }
 }
@@ -77,6 +294,14 @@ pub fn get_js_constructor_from_acttype(act_type: &TypeAct) -> String {
         TypeAct::BigInt => "BigInt".to_string(),
         TypeAct::Boolean => "Boolean".to_string(),
         TypeAct::Symbol => "Symbol".to_string(),
+        TypeAct::Null => "".to_string(),
+        TypeAct::Undefined => "".to_string(),
+        TypeAct::Void => "".to_string(),
+        TypeAct::Never => "".to_string(),
+        TypeAct::NonPrimitive => "".to_string(),
+        TypeAct::Function => "".to_string(),
+        TypeAct::Signature(_, _) => "".to_string(),
+        TypeAct::Any(_) => "".to_string(),
         TypeAct::Float32Array => "new Float32Array".to_string(),
         TypeAct::Float64Array => "new Float64Array".to_string(),
         TypeAct::Int8Array => "new Int8Array".to_string(),
@@ -88,6 +313,26 @@ pub fn get_js_constructor_from_acttype(act_type: &TypeAct) -> String {
         TypeAct::Uint32Array => "new Uint32Array".to_string(),
         TypeAct::BigInt64Array => "new BigInt64Array".to_string(),
         TypeAct::BigUint64Array => "new BigUint64Array".to_string(),
+        TypeAct::Date => "new Date".to_string(),
+        TypeAct::RegExp => "new RegExp".to_string(),
+        TypeAct::Error => "new Error".to_string(),
+        TypeAct::ArrayBuffer => "".to_string(),
+        TypeAct::DataView => "".to_string(),
+        TypeAct::URL => "new URL".to_string(),
+        TypeAct::Map(_, _) => "new Map".to_string(),
+        TypeAct::Set(_) => "new Set".to_string(),
+        TypeAct::Promise(_) => "Promise.resolve".to_string(),
+        TypeAct::Union(_) => "".to_string(),
+        TypeAct::Intersection(_) => "".to_string(),
+        TypeAct::Array(_) => "".to_string(),
+        TypeAct::Tuple(_) => "".to_string(),
+        TypeAct::Object(_) => "".to_string(),
+        TypeAct::Record(_) => "".to_string(),
+        TypeAct::TemplateLiteral(_) => "".to_string(),
+        TypeAct::Literal(_) => "".to_string(),
+        TypeAct::Reference(_, _) => "".to_string(),
+        TypeAct::Class(_) => "".to_string(),
+        TypeAct::Unchecked(_) => "".to_string(),
         TypeAct::Unknown => "".to_string(),
     }
 }
@@ -99,8 +344,14 @@ pub fn get_acttype_from_string(type_str: &str) -> TypeAct {
         "bigint" => TypeAct::BigInt,
         "boolean" => TypeAct::Boolean,
         "symbol" => TypeAct::Symbol,
-        "object" => TypeAct::Unknown,
-        "unknown" => TypeAct::Unknown,
+        "null" => TypeAct::Null,
+        "undefined" => TypeAct::Undefined,
+        "void" => TypeAct::Void,
+        "never" => TypeAct::Never,
+        "object" => TypeAct::NonPrimitive,
+        "Function" => TypeAct::Function,
+        "any" => TypeAct::Any("any".to_string()),
+        "unknown" => TypeAct::Any("unknown".to_string()),
         "Float32Array" => TypeAct::Float32Array,
         "Float64Array" => TypeAct::Float64Array,
         "Int8Array" => TypeAct::Int8Array,
@@ -112,6 +363,15 @@ pub fn get_acttype_from_string(type_str: &str) -> TypeAct {
         "Uint32Array" => TypeAct::Uint32Array,
         "BigInt64Array" => TypeAct::BigInt64Array,
         "BigUint64Array" => TypeAct::BigUint64Array,
+        "Date" => TypeAct::Date,
+        "RegExp" => TypeAct::RegExp,
+        "Error" => TypeAct::Error,
+        "ArrayBuffer" => TypeAct::ArrayBuffer,
+        "DataView" => TypeAct::DataView,
+        "URL" => TypeAct::URL,
+        "Map" => TypeAct::Map(Box::new(TypeAct::Unknown), Box::new(TypeAct::Unknown)),
+        "Set" => TypeAct::Set(Box::new(TypeAct::Unknown)),
+        "Promise" => TypeAct::Promise(Box::new(TypeAct::Unknown)),
         _ => TypeAct::Unknown,
     }
 }
@@ -123,6 +383,14 @@ pub fn get_typeinfo_operator_from_acttype(act_type: &TypeAct) -> String {
         TypeAct::BigInt => "typeof".to_string(),
         TypeAct::Boolean => "typeof".to_string(),
         TypeAct::Symbol => "typeof".to_string(),
+        TypeAct::Null => "===".to_string(),
+        TypeAct::Undefined => "typeof".to_string(),
+        TypeAct::Void => "===".to_string(),
+        TypeAct::Never => "".to_string(),
+        TypeAct::NonPrimitive => "".to_string(),
+        TypeAct::Function => "typeof".to_string(),
+        TypeAct::Signature(_, _) => "typeof".to_string(),
+        TypeAct::Any(_) => "".to_string(),
         TypeAct::Float32Array => "instanceof".to_string(),
         TypeAct::Float64Array => "instanceof".to_string(),
         TypeAct::Int8Array => "instanceof".to_string(),
@@ -134,6 +402,56 @@ pub fn get_typeinfo_operator_from_acttype(act_type: &TypeAct) -> String {
         TypeAct::Uint32Array => "instanceof".to_string(),
         TypeAct::BigInt64Array => "instanceof".to_string(),
         TypeAct::BigUint64Array => "instanceof".to_string(),
+        TypeAct::Date => "instanceof".to_string(),
+        TypeAct::RegExp => "instanceof".to_string(),
+        TypeAct::Error => "instanceof".to_string(),
+        TypeAct::ArrayBuffer => "instanceof".to_string(),
+        TypeAct::DataView => "instanceof".to_string(),
+        TypeAct::URL => "instanceof".to_string(),
+        TypeAct::Map(_, _) => "instanceof".to_string(),
+        TypeAct::Set(_) => "instanceof".to_string(),
+        TypeAct::Promise(_) => "instanceof".to_string(),
+        TypeAct::Union(_) => "".to_string(),
+        TypeAct::Intersection(_) => "".to_string(),
+        TypeAct::Array(_) => "".to_string(),
+        TypeAct::Tuple(_) => "".to_string(),
+        TypeAct::Object(_) => "".to_string(),
+        TypeAct::Record(_) => "".to_string(),
+        TypeAct::TemplateLiteral(_) => "".to_string(),
+        TypeAct::Literal(_) => "===".to_string(),
+        TypeAct::Reference(_, _) => "".to_string(),
+        TypeAct::Class(_) => "instanceof".to_string(),
+        TypeAct::Unchecked(_) => "".to_string(),
         TypeAct::Unknown => "".to_string(),
     }
 }
+
+pub fn get_unchecked_reasons(act_type: &TypeAct) -> Vec<String> {
+    match act_type {
+        TypeAct::Unchecked(reason) => vec![reason.to_string()],
+        TypeAct::Union(types) | TypeAct::Intersection(types) | TypeAct::Reference(_, types) => {
+            types.iter().flat_map(get_unchecked_reasons).collect()
+        }
+        TypeAct::Array(elem_type) | TypeAct::Set(elem_type) | TypeAct::Record(elem_type) => {
+            get_unchecked_reasons(elem_type)
+        }
+        TypeAct::Map(key_type, value_type) => {
+            let mut reasons = get_unchecked_reasons(key_type);
+            reasons.extend(get_unchecked_reasons(value_type));
+            reasons
+        }
+        TypeAct::Tuple(elements) => elements
+            .iter()
+            .flat_map(|element| match element {
+                TupleElementAct::Required(act_type)
+                | TupleElementAct::Optional(act_type)
+                | TupleElementAct::Rest(act_type) => get_unchecked_reasons(act_type),
+            })
+            .collect(),
+        TypeAct::Object(properties) => properties
+            .iter()
+            .flat_map(|property| get_unchecked_reasons(&property.act_type))
+            .collect(),
+        _ => vec![],
+    }
+}
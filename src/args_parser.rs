@@ -1,6 +1,6 @@
 use clap::Parser;
 
-use crate::act_patch::PatchType;
+use crate::act_patch::{ArrayCheckMode, PatchType};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -13,4 +13,28 @@ pub struct ActArgs {
 
     #[arg(value_enum, default_value_t = PatchType::Warning)]
     pub patch_type: PatchType,
+
+    /// How the elements of array parameters are checked
+    #[arg(long, value_enum, default_value_t = ArrayCheckMode::Full)]
+    pub array_check: ArrayCheckMode,
+
+    /// Number of elements checked with `--array-check sample`
+    #[arg(long, default_value_t = 10)]
+    pub array_sample_size: usize,
+
+    /// Also check the entries of `Map` and `Set` parameters
+    #[arg(long)]
+    pub deep_collections: bool,
+
+    /// Reject `Date` parameters holding an invalid date
+    #[arg(long)]
+    pub reject_invalid_dates: bool,
+
+    /// Wrap function parameters to check the arguments and result of each call
+    #[arg(long)]
+    pub wrap_callbacks: bool,
+
+    /// Check unannotated parameters against the type of their literal default
+    #[arg(long)]
+    pub infer_defaults: bool,
 }
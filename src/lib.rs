@@ -1,5 +1,6 @@
 pub mod act_patch;
 pub mod act_process;
+pub mod act_scope;
 pub mod act_structs;
 pub mod patch_index_helper;
 pub mod args_parser;
\ No newline at end of file
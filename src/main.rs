@@ -1,6 +1,9 @@
-use act_lib::{act_process::process_file, args_parser::ActArgs};
+use act_lib::{
+    act_process::{get_project_types_act, process_file},
+    args_parser::ActArgs,
+};
 use clap::Parser;
-use std::{fs, path::PathBuf, println, thread, time::Instant};
+use std::{fs, path::PathBuf, println, sync::Arc, thread, time::Instant};
 
 fn get_files_paths(folder_path: String) -> Vec<PathBuf> {
     let files = fs::read_dir(folder_path).expect("Unable to read directory");
@@ -25,10 +28,13 @@ fn main() {
     let args = ActArgs::parse();
     let folder_path = args.folder_path;
     let files = get_files_paths(folder_path);
+    // Index the types of every file first so imported types can be resolved
+    let project_types = Arc::new(get_project_types_act(&files));
     for file_path in files {
+        let project_types = Arc::clone(&project_types);
         thread::Builder::new()
             .name(file_path.to_string_lossy().to_string())
-            .spawn(move || process_file(file_path).unwrap_or(()))
+            .spawn(move || process_file(file_path, &project_types).unwrap_or(()))
             .unwrap_or_else(|err| {
                 println!("{:?}", err);
                 panic!();
@@ -46,40 +52,44 @@ fn main() {
 #[cfg(test)]
 mod tests {
 
-    use super::process_file;
+    use super::{get_project_types_act, process_file};
     use std::{fs, path::PathBuf, println};
 
     #[test]
     fn simple_function_test() {
         // TODO:
-        // create tests folder if not exists
-        fs::create_dir("tests").unwrap_or_else(|err| {
+        // something like that
+        // ActArgs::parse_from(&["act", "-f", "tests"]);
+        fs::create_dir_all("tests").unwrap_or_else(|err| {
             println!("{:?}", err);
             panic!();
         });
-        // something like that
-        // ActArgs::parse_from(&["act", "-f", "tests"]);
         // create simple_function.ts file
-        let file_path = PathBuf::from("tests/simple_function.ts");
+        let file_path = PathBuf::from("./tests/simple_function.ts");
         let file_data = r#"
         function test(a: string, b: number): string {
             return a + b;
         }"#;
-        let expected_result_file_data = r#"
-        function test(a: string, b: number): string {
-            return a + b;
-        }"#;
         fs::write(&file_path, file_data).unwrap_or_else(|err| {
             println!("{:?}", err);
             panic!();
         });
-        process_file(file_path.clone()).unwrap_or(());
+        let project_types = get_project_types_act(std::slice::from_ref(&file_path));
+        process_file(file_path.clone(), &project_types).unwrap_or(());
         // change file_path root folder path to out_folder_path
         let result = fs::read_to_string(&file_path).unwrap_or_else(|err| {
             println!("{:?}", err);
             panic!();
         });
+        fs::remove_file(&file_path).unwrap_or(());
+        fs::remove_dir("tests").unwrap_or(());
 
-        assert!(result == expected_result_file_data)
+        assert!(result.contains("if(!(typeof a === 'string'))"));
+        assert!(result.contains("if(!(typeof b === 'number'))"));
+        assert!(result.ends_with(
+            r#"
+            return a + b;
+        }"#
+        ));
     }
 }
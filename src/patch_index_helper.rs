@@ -1,6 +1,7 @@
 pub struct ModifiedIndex {
     pub pos: u32,
     pub size: u32,
+    pub replaced_size: u32,
 }
 
 pub struct PatchIndexHelper {
@@ -17,7 +18,15 @@ impl PatchIndexHelper {
     }
 
     pub fn register_patched_index(&mut self, index: u32, size: u32) {
-        let modified_index = ModifiedIndex { pos: index, size };
+        self.register_replaced_index(index, size, 0);
+    }
+
+    pub fn register_replaced_index(&mut self, index: u32, size: u32, replaced_size: u32) {
+        let modified_index = ModifiedIndex {
+            pos: index,
+            size,
+            replaced_size,
+        };
         self.indexes_modified.push(modified_index);
     }
 
@@ -26,6 +35,7 @@ impl PatchIndexHelper {
         for index_modify in &self.indexes_modified {
             if original_index >= index_modify.pos {
                 drifted_index += index_modify.size;
+                drifted_index -= index_modify.replaced_size;
             }
         }
         drifted_index